@@ -0,0 +1,363 @@
+// alert rules, evaluated on the merged data after every message. a rule has to match for a
+// while before its banner shows and clears only once the value is back past a margin, so a
+// value around the threshold does not make the banner flicker
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::time::Duration;
+
+use crate::clock::Tick;
+use crate::prototypes::types::NewUiStruct;
+use crate::state::SystemState;
+use crate::TextBuffer;
+
+// the battery has to be this much above the threshold again to clear the alert, in percent
+const BATTERY_MARGIN: f32 = 2.0;
+// same for the grid import, in kW
+const IMPORT_MARGIN: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertError {
+    UnknownRule,
+    InvalidValue,
+    InvalidDuration,
+}
+
+impl AlertError {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AlertError::UnknownRule => "unknown alert rule",
+            AlertError::InvalidValue => "invalid alert value",
+            AlertError::InvalidDuration => "invalid alert duration",
+        }
+    }
+}
+
+impl core::fmt::Display for AlertError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl core::error::Error for AlertError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Condition {
+    // state of charge in percent
+    BatteryBelow(f32),
+    // kW drawn from the grid
+    GridImportAbove(f32),
+    SystemError,
+    // the time without a message is the duration of the rule
+    NoData,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rule {
+    pub condition: Condition,
+    // how long the condition has to hold before the alert shows
+    pub after: Duration,
+}
+
+impl Rule {
+    pub fn new(condition: Condition, after: Duration) -> Self {
+        Rule { condition, after }
+    }
+    // formatted on the stack, the banner is drawn again with every full repaint
+    pub fn message(&self) -> TextBuffer {
+        let mut message = TextBuffer::new();
+        // the messages are far shorter than a text can be
+        let _ = match self.condition {
+            Condition::BatteryBelow(soc) => write!(message, "Battery below {:.0}%", soc),
+            Condition::GridImportAbove(kw) => write!(message, "Grid import above {:.1} kW", kw),
+            Condition::SystemError => message.write_str("System error"),
+            Condition::NoData => write!(message, "No data for {}", ShortDuration(self.after)),
+        };
+        message
+    }
+    // parses "battery<10", "import>5@10m", "error" and "nodata@15m"
+    pub fn parse(rule: &str) -> Result<Self, AlertError> {
+        let (condition, after) = match rule.trim().split_once('@') {
+            Some((condition, after)) => (condition.trim(), parse_duration(after)?),
+            None => (rule.trim(), Duration::ZERO),
+        };
+        let value = |value: &str| match value.trim().parse::<f32>() {
+            Ok(value) if value.is_finite() && value >= 0.0 => Ok(value),
+            _ => Err(AlertError::InvalidValue),
+        };
+        let condition = if let Some(soc) = condition.strip_prefix("battery<") {
+            Condition::BatteryBelow(value(soc)?)
+        } else if let Some(kw) = condition.strip_prefix("import>") {
+            Condition::GridImportAbove(value(kw)?)
+        } else if condition == "error" {
+            Condition::SystemError
+        } else if condition == "nodata" {
+            if after.is_zero() {
+                return Err(AlertError::InvalidDuration);
+            }
+            Condition::NoData
+        } else {
+            return Err(AlertError::UnknownRule);
+        };
+        Ok(Rule::new(condition, after))
+    }
+}
+
+// "30s", "10m" or "1h"
+fn parse_duration(duration: &str) -> Result<Duration, AlertError> {
+    let duration = duration.trim();
+    let (value, unit) = if let Some(value) = duration.strip_suffix('s') {
+        (value, 1)
+    } else if let Some(value) = duration.strip_suffix('m') {
+        (value, 60)
+    } else if let Some(value) = duration.strip_suffix('h') {
+        (value, 3600)
+    } else {
+        return Err(AlertError::InvalidDuration);
+    };
+    let value: u64 = value.parse().map_err(|_| AlertError::InvalidDuration)?;
+    let secs = value.checked_mul(unit).ok_or(AlertError::InvalidDuration)?;
+    Ok(Duration::from_secs(secs))
+}
+
+struct ShortDuration(Duration);
+
+impl core::fmt::Display for ShortDuration {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let secs = self.0.as_secs();
+        // the largest unit that fits without a remainder
+        match (secs % 3600, secs % 60) {
+            (0, _) => write!(f, "{}h", secs / 3600),
+            (_, 0) => write!(f, "{}m", secs / 60),
+            _ => write!(f, "{}s", secs),
+        }
+    }
+}
+
+// rules are separated by commas
+pub fn parse_rules(rules: &str) -> Result<Vec<Rule>, AlertError> {
+    rules
+        .split(',')
+        .filter(|rule| !rule.trim().is_empty())
+        .map(Rule::parse)
+        .collect()
+}
+
+// the rules of a message or the configuration, without any the built-in ones are used
+pub fn rules_or_default(rules: &str) -> Result<Vec<Rule>, AlertError> {
+    if rules.trim().is_empty() {
+        return Ok(default_rules());
+    }
+    parse_rules(rules)
+}
+
+pub fn default_rules() -> Vec<Rule> {
+    vec![
+        Rule::new(Condition::SystemError, Duration::ZERO),
+        Rule::new(Condition::NoData, Duration::from_secs(15 * 60)),
+        Rule::new(Condition::BatteryBelow(10.0), Duration::ZERO),
+        Rule::new(
+            Condition::GridImportAbove(5.0),
+            Duration::from_secs(10 * 60),
+        ),
+    ]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Check {
+    Matches,
+    Cleared,
+    // inside the margin or without a value, the alert stays as it is
+    Hold,
+}
+
+#[derive(Debug)]
+struct RuleState {
+    rule: Rule,
+    since: Option<Tick>,
+    active: bool,
+}
+
+#[derive(Debug)]
+pub struct AlertEngine {
+    rules: Vec<RuleState>,
+}
+
+impl Default for AlertEngine {
+    fn default() -> Self {
+        AlertEngine::new(default_rules())
+    }
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        let mut engine = AlertEngine { rules: Vec::new() };
+        engine.set_rules(rules);
+        engine
+    }
+    // replaces the rules, active alerts are dropped
+    pub fn set_rules(&mut self, rules: Vec<Rule>) {
+        self.rules = rules
+            .into_iter()
+            .map(|rule| RuleState {
+                rule,
+                since: None,
+                active: false,
+            })
+            .collect();
+    }
+    pub fn rules(&self) -> impl Iterator<Item = &Rule> {
+        self.rules.iter().map(|state| &state.rule)
+    }
+    // returns true if an alert started or cleared
+    pub fn evaluate(&mut self, ui: &NewUiStruct, last_message: Option<Tick>, now: Tick) -> bool {
+        let mut changed = false;
+        for state in self.rules.iter_mut() {
+            let check = check(&state.rule, ui, last_message, now);
+            let was_active = state.active;
+            match check {
+                Check::Matches => {
+                    let since = *state.since.get_or_insert(now);
+                    // no data counts from the last message, the time is part of the check
+                    if state.rule.condition == Condition::NoData
+                        || now.duration_since(since) >= state.rule.after
+                    {
+                        state.active = true;
+                    }
+                }
+                Check::Cleared => {
+                    state.since = None;
+                    state.active = false;
+                }
+                Check::Hold => {
+                    if !state.active {
+                        state.since = None;
+                    }
+                }
+            }
+            changed |= was_active != state.active;
+        }
+        changed
+    }
+    // the first rule wins if several match, the rules are ordered by importance
+    pub fn active(&self) -> Option<TextBuffer> {
+        self.rules
+            .iter()
+            .find(|state| state.active)
+            .map(|state| state.rule.message())
+    }
+}
+
+fn check(rule: &Rule, ui: &NewUiStruct, last_message: Option<Tick>, now: Tick) -> Check {
+    let value = |v: &Option<String>| v.as_deref().and_then(|v| v.trim().parse::<f32>().ok());
+    match rule.condition {
+        Condition::BatteryBelow(threshold) => match value(&ui.gui_bat_data_fuel_charge) {
+            Some(soc) if soc < threshold => Check::Matches,
+            Some(soc) if soc >= threshold + BATTERY_MARGIN => Check::Cleared,
+            _ => Check::Hold,
+        },
+        Condition::GridImportAbove(threshold) => match value(&ui.gui_grid_pow) {
+            Some(kw) if kw > threshold => Check::Matches,
+            Some(kw) if kw <= threshold - IMPORT_MARGIN => Check::Cleared,
+            _ => Check::Hold,
+        },
+        Condition::SystemError => match &ui.stat_state {
+            Some(stat_state) if SystemState::parse(stat_state) == SystemState::Error => {
+                Check::Matches
+            }
+            Some(_) => Check::Cleared,
+            None => Check::Hold,
+        },
+        Condition::NoData => match last_message {
+            Some(last) if now.duration_since(last) >= rule.after => Check::Matches,
+            Some(_) => Check::Cleared,
+            None => Check::Hold,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ui(soc: &str, grid: &str) -> NewUiStruct {
+        NewUiStruct {
+            gui_bat_data_fuel_charge: Some(soc.to_string()),
+            gui_grid_pow: Some(grid.to_string()),
+            stat_state: Some("21".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_rules() {
+        assert_eq!(
+            parse_rules("battery<10, import>5@10m,error, nodata@15m").unwrap(),
+            vec![
+                Rule::new(Condition::BatteryBelow(10.0), Duration::ZERO),
+                Rule::new(Condition::GridImportAbove(5.0), Duration::from_secs(600)),
+                Rule::new(Condition::SystemError, Duration::ZERO),
+                Rule::new(Condition::NoData, Duration::from_secs(900)),
+            ]
+        );
+        assert_eq!(Rule::parse("battery<x"), Err(AlertError::InvalidValue));
+        assert_eq!(
+            Rule::parse("import>5@10d"),
+            Err(AlertError::InvalidDuration)
+        );
+        assert_eq!(Rule::parse("nodata"), Err(AlertError::InvalidDuration));
+        assert_eq!(
+            Rule::parse("nodata@9999999999999999h"),
+            Err(AlertError::InvalidDuration)
+        );
+        assert_eq!(Rule::parse("solar<1"), Err(AlertError::UnknownRule));
+        assert_eq!(
+            Rule::parse("nodata@15m").unwrap().message(),
+            "No data for 15m"
+        );
+    }
+
+    #[test]
+    fn battery_alert_clears_with_hysteresis() {
+        let mut engine = AlertEngine::new(vec![Rule::parse("battery<10").unwrap()]);
+        let now = Tick::default();
+        assert!(!engine.evaluate(&ui("12.0", "0.0"), Some(now), now));
+        assert!(engine.evaluate(&ui("9.5", "0.0"), Some(now), now));
+        assert_eq!(engine.active().as_deref(), Some("Battery below 10%"));
+        // back above the threshold, but not past the margin yet
+        assert!(!engine.evaluate(&ui("10.5", "0.0"), Some(now), now));
+        assert!(engine.active().is_some());
+        assert!(engine.evaluate(&ui("12.0", "0.0"), Some(now), now));
+        assert_eq!(engine.active(), None);
+    }
+
+    #[test]
+    fn import_alert_needs_to_last() {
+        let mut engine = AlertEngine::new(vec![Rule::parse("import>5@10m").unwrap()]);
+        let start = Tick::default();
+        let at = |minutes: u64| start + Duration::from_secs(minutes * 60);
+        engine.evaluate(&ui("50", "6.0"), Some(at(0)), at(0));
+        assert!(!engine.evaluate(&ui("50", "6.0"), Some(at(9)), at(9)));
+        // a dip below the threshold starts the time over
+        engine.evaluate(&ui("50", "4.0"), Some(at(10)), at(10));
+        assert!(!engine.evaluate(&ui("50", "6.0"), Some(at(11)), at(11)));
+        assert!(engine.evaluate(&ui("50", "6.0"), Some(at(21)), at(21)));
+        assert_eq!(engine.active().as_deref(), Some("Grid import above 5.0 kW"));
+    }
+
+    #[test]
+    fn no_data_and_priorities() {
+        let mut engine = AlertEngine::default();
+        let start = Tick::default();
+        let mut data = ui("5.0", "0.0");
+        engine.evaluate(&data, Some(start), start);
+        assert_eq!(engine.active().as_deref(), Some("Battery below 10%"));
+        // more important rules come first
+        assert!(engine.evaluate(&data, Some(start), start + Duration::from_secs(15 * 60)));
+        assert_eq!(engine.active().as_deref(), Some("No data for 15m"));
+        data.stat_state = Some("SYSTEM ERROR".to_string());
+        engine.evaluate(&data, Some(start), start);
+        assert_eq!(engine.active().as_deref(), Some("System error"));
+    }
+}
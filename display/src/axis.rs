@@ -0,0 +1,127 @@
+// scaling for the y axis of the charts. the axis maximum is rounded up to a "nice" value, so
+// the tick labels stay short enough for the small font
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisScale {
+    pub max: f32,
+    pub step: f32,
+}
+
+impl AxisScale {
+    // max_ticks is the maximum number of ticks above zero
+    pub fn new(max_value: f32, max_ticks: u32) -> Self {
+        let max_value = if max_value.is_finite() && max_value > 0.0 {
+            max_value
+        } else {
+            1.0
+        };
+        let raw_step = max_value / max_ticks.max(1) as f32;
+
+        // we avoid log10 and powf here, so this also works without std
+        let mut magnitude = 1.0;
+        while raw_step >= magnitude * 10.0 {
+            magnitude *= 10.0;
+        }
+        while raw_step < magnitude {
+            magnitude /= 10.0;
+        }
+        let residual = raw_step / magnitude;
+        let nice = if residual <= 1.0 {
+            1.0
+        } else if residual <= 2.0 {
+            2.0
+        } else if residual <= 2.5 {
+            2.5
+        } else if residual <= 5.0 {
+            5.0
+        } else {
+            10.0
+        };
+        let step = nice * magnitude;
+        // the small epsilon keeps exact multiples from getting an additional step
+        let steps = (max_value / step - 1e-4) as u32 + 1;
+        AxisScale {
+            max: steps as f32 * step,
+            step,
+        }
+    }
+    // the tick values, starting at 0 and ending at the axis maximum
+    pub fn ticks(&self) -> impl Iterator<Item = f32> {
+        let step = self.step;
+        let count = (self.max / step + 0.5) as u32;
+        (0..=count).map(move |i| i as f32 * step)
+    }
+    // number of decimals the labels need
+    pub fn decimals(&self) -> usize {
+        let mut step = self.step;
+        let mut decimals = 0;
+        while decimals < 3 && (step - (step + 0.5) as u32 as f32).abs() > 1e-3 {
+            step *= 10.0;
+            decimals += 1;
+        }
+        decimals
+    }
+    // height in pixels above the baseline
+    pub fn to_pixels(&self, value: f32, height: u32) -> i32 {
+        let value = value.clamp(0.0, self.max);
+        (value / self.max * height as f32 + 0.5) as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scale(max_value: f32, max_ticks: u32) -> (f32, f32) {
+        let scale = AxisScale::new(max_value, max_ticks);
+        (scale.max, scale.step)
+    }
+
+    #[test]
+    fn rounds_up_to_nice_values() {
+        assert_eq!(scale(4.3, 3), (6.0, 2.0));
+        assert_eq!(scale(9.6, 3), (10.0, 5.0));
+        assert_eq!(scale(7.1, 3), (7.5, 2.5));
+        assert_eq!(scale(0.8, 3), (1.0, 0.5));
+        assert_eq!(scale(25.0, 3), (30.0, 10.0));
+    }
+
+    #[test]
+    fn exact_multiples_are_not_extended() {
+        assert_eq!(scale(6.0, 3), (6.0, 2.0));
+        assert_eq!(scale(1.0, 2), (1.0, 0.5));
+    }
+
+    #[test]
+    fn invalid_maximum_falls_back_to_one() {
+        assert_eq!(AxisScale::new(0.0, 3), AxisScale::new(1.0, 3));
+        assert_eq!(AxisScale::new(-2.0, 3), AxisScale::new(1.0, 3));
+        assert_eq!(AxisScale::new(f32::NAN, 3), AxisScale::new(1.0, 3));
+    }
+
+    #[test]
+    fn ticks_cover_the_axis() {
+        let ticks: Vec<f32> = AxisScale::new(4.3, 3).ticks().collect();
+        assert_eq!(ticks, vec![0.0, 2.0, 4.0, 6.0]);
+        let ticks: Vec<f32> = AxisScale::new(0.8, 3).ticks().collect();
+        assert_eq!(ticks, vec![0.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn decimals_match_the_step() {
+        assert_eq!(AxisScale::new(4.3, 3).decimals(), 0);
+        assert_eq!(AxisScale::new(0.8, 3).decimals(), 1);
+        assert_eq!(AxisScale::new(7.1, 3).decimals(), 1);
+        assert_eq!(AxisScale::new(0.05, 3).decimals(), 2);
+    }
+
+    #[test]
+    fn values_are_mapped_to_pixels() {
+        let scale = AxisScale::new(4.3, 3);
+        assert_eq!(scale.to_pixels(0.0, 45), 0);
+        assert_eq!(scale.to_pixels(3.0, 45), 23);
+        assert_eq!(scale.to_pixels(6.0, 45), 45);
+        // values outside of the axis are clamped
+        assert_eq!(scale.to_pixels(8.0, 45), 45);
+        assert_eq!(scale.to_pixels(-1.0, 45), 0);
+    }
+}
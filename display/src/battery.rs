@@ -0,0 +1,129 @@
+// battery details for the battery page. the state of charge and the power come with every
+// message, the detailed values only when the server has them
+use crate::prototypes::types::{BatteryData, NewUiStruct};
+
+// below this the battery counts as idle, otherwise the estimate runs into days
+const MIN_POWER_KW: f32 = 0.05;
+// longer estimates are not shown
+const MAX_ESTIMATE_MINUTES: u32 = 99 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Estimate {
+    Idle,
+    ToFull(u32),
+    ToEmpty(u32),
+}
+
+// like "Full in 2h 05m"
+impl core::fmt::Display for Estimate {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Estimate::Idle => f.write_str("Idle"),
+            Estimate::ToFull(minutes) => {
+                write!(f, "Full in {}h {:02}m", minutes / 60, minutes % 60)
+            }
+            Estimate::ToEmpty(minutes) => {
+                write!(f, "Empty in {}h {:02}m", minutes / 60, minutes % 60)
+            }
+        }
+    }
+}
+
+// minutes until the battery is full or empty at the current power. power is positive while
+// charging, the capacity is the usable capacity in kWh
+pub fn estimate(soc_percent: f32, power_kw: f32, capacity_kwh: f32) -> Option<Estimate> {
+    if !soc_percent.is_finite() || !power_kw.is_finite() || !capacity_kwh.is_finite() {
+        return None;
+    }
+    if capacity_kwh <= 0.0 {
+        return None;
+    }
+    let soc = soc_percent.clamp(0.0, 100.0) / 100.0;
+    let (energy_kwh, estimate): (f32, fn(u32) -> Estimate) = if power_kw >= MIN_POWER_KW {
+        (capacity_kwh * (1.0 - soc), Estimate::ToFull)
+    } else if power_kw <= -MIN_POWER_KW {
+        (capacity_kwh * soc, Estimate::ToEmpty)
+    } else {
+        return Some(Estimate::Idle);
+    };
+    let minutes = energy_kwh / power_kw.abs() * 60.0;
+    if minutes > MAX_ESTIMATE_MINUTES as f32 {
+        return None;
+    }
+    // rounded, the minutes are never negative
+    Some(estimate((minutes + 0.5) as u32))
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct BatteryStatus {
+    pub details: Option<BatteryData>,
+    pub soc_percent: Option<f32>,
+    pub power_kw: Option<f32>,
+}
+
+impl BatteryStatus {
+    // missing fields keep their previous values, like on the dashboard
+    pub fn update(&mut self, ui_data: &NewUiStruct) {
+        if let Some(details) = ui_data.battery {
+            self.details = Some(details);
+        }
+        if let Some(soc) = &ui_data.gui_bat_data_fuel_charge {
+            self.soc_percent = soc.trim().parse().ok();
+        }
+        if let Some(power) = &ui_data.gui_bat_data_power {
+            self.power_kw = power.trim().parse().ok();
+        }
+    }
+    pub fn estimate(&self) -> Option<Estimate> {
+        estimate(
+            self.soc_percent?,
+            self.power_kw?,
+            self.details.as_ref()?.capacity_kwh,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates() {
+        // 5 kWh missing at 2 kW
+        assert_eq!(estimate(50.0, 2.0, 10.0), Some(Estimate::ToFull(150)));
+        // 2.5 kWh left at 1 kW
+        assert_eq!(estimate(25.0, -1.0, 10.0), Some(Estimate::ToEmpty(150)));
+        assert_eq!(estimate(50.0, 0.01, 10.0), Some(Estimate::Idle));
+        assert_eq!(estimate(100.0, 2.0, 10.0), Some(Estimate::ToFull(0)));
+    }
+
+    #[test]
+    fn estimates_without_meaningful_values() {
+        assert_eq!(estimate(50.0, 2.0, 0.0), None);
+        assert_eq!(estimate(f32::NAN, 2.0, 10.0), None);
+        // 50 kWh at 0.1 kW would take more than 99 hours
+        assert_eq!(estimate(50.0, 0.1, 100.0), None);
+    }
+
+    #[test]
+    fn labels() {
+        assert_eq!(Estimate::ToFull(125).to_string(), "Full in 2h 05m");
+        assert_eq!(Estimate::ToEmpty(59).to_string(), "Empty in 0h 59m");
+    }
+
+    #[test]
+    fn status_keeps_previous_values() {
+        let mut status = BatteryStatus::default();
+        status.update(&NewUiStruct {
+            gui_bat_data_fuel_charge: Some("80.0".to_string()),
+            gui_bat_data_power: Some("-2.00".to_string()),
+            battery: Some(BatteryData {
+                capacity_kwh: 10.0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        status.update(&NewUiStruct::default());
+        assert_eq!(status.estimate(), Some(Estimate::ToEmpty(240)));
+    }
+}
@@ -0,0 +1,217 @@
+// pictures rendered by the server, for displays that show something the firmware knows nothing
+// about. they are put together on a screen the size of the canvas, so a picture that only
+// covers a part stays in place and the page can be redrawn from the screen at any time
+use alloc::vec;
+use alloc::vec::Vec;
+
+use embedded_graphics::prelude::{Point, Size};
+use embedded_graphics::primitives::Rectangle;
+
+use crate::panel::{CANVAS_HEIGHT, CANVAS_WIDTH};
+use crate::prototypes::types::Bitmap;
+
+const SCREEN_ROW_BYTES: usize = row_bytes(CANVAS_WIDTH);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitmapError {
+    Empty,
+    OutOfBounds,
+    InvalidLength,
+    InvalidRle,
+}
+
+impl BitmapError {
+    pub fn label(&self) -> &'static str {
+        match self {
+            BitmapError::Empty => "empty bitmap",
+            BitmapError::OutOfBounds => "bitmap outside of the canvas",
+            BitmapError::InvalidLength => "bitmap size does not match",
+            BitmapError::InvalidRle => "invalid bitmap encoding",
+        }
+    }
+}
+
+impl core::fmt::Display for BitmapError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl core::error::Error for BitmapError {}
+
+// every row starts on a new byte
+const fn row_bytes(width: u32) -> usize {
+    (width as usize).div_ceil(8)
+}
+
+// a bitmap that fits on the canvas, with the pixels unpacked from the run length encoding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub region: Rectangle,
+    // one bit per pixel, the leftmost pixel in the highest bit, set bits are black
+    pub rows: Vec<u8>,
+    pub full_refresh: bool,
+}
+
+impl Image {
+    pub fn decode(bitmap: &Bitmap) -> Result<Self, BitmapError> {
+        if bitmap.width == 0 || bitmap.height == 0 {
+            return Err(BitmapError::Empty);
+        }
+        let fits =
+            |start: u32, len: u32, max: u32| start.checked_add(len).is_some_and(|end| end <= max);
+        if !fits(bitmap.x, bitmap.width, CANVAS_WIDTH)
+            || !fits(bitmap.y, bitmap.height, CANVAS_HEIGHT)
+        {
+            return Err(BitmapError::OutOfBounds);
+        }
+        let len = row_bytes(bitmap.width) * bitmap.height as usize;
+        let rows = if bitmap.rle {
+            decode_rle(&bitmap.pixels, len)?
+        } else {
+            bitmap.pixels.clone()
+        };
+        if rows.len() != len {
+            return Err(BitmapError::InvalidLength);
+        }
+        Ok(Image {
+            region: Rectangle::new(
+                Point::new(bitmap.x as i32, bitmap.y as i32),
+                Size::new(bitmap.width, bitmap.height),
+            ),
+            rows,
+            full_refresh: bitmap.full_refresh,
+        })
+    }
+    fn is_black(&self, x: usize, y: usize) -> bool {
+        let byte = self.rows[y * row_bytes(self.region.size.width) + x / 8];
+        byte & (0x80 >> (x % 8)) != 0
+    }
+}
+
+// pairs of a count and the byte that is repeated. a picture is never longer than len, so a
+// broken count can not make it grow without end
+fn decode_rle(encoded: &[u8], len: usize) -> Result<Vec<u8>, BitmapError> {
+    if !encoded.len().is_multiple_of(2) {
+        return Err(BitmapError::InvalidRle);
+    }
+    let mut decoded = Vec::with_capacity(len);
+    for pair in encoded.chunks_exact(2) {
+        let (count, byte) = (pair[0] as usize, pair[1]);
+        if count == 0 || decoded.len() + count > len {
+            return Err(BitmapError::InvalidRle);
+        }
+        decoded.extend(core::iter::repeat_n(byte, count));
+    }
+    Ok(decoded)
+}
+
+// everything the server drew so far, white where it did not draw
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screen {
+    rows: Vec<u8>,
+}
+
+impl Default for Screen {
+    fn default() -> Self {
+        Screen {
+            rows: vec![0; SCREEN_ROW_BYTES * CANVAS_HEIGHT as usize],
+        }
+    }
+}
+
+impl Screen {
+    pub fn blit(&mut self, image: &Image) {
+        let top_left = image.region.top_left;
+        for y in 0..image.region.size.height as usize {
+            for x in 0..image.region.size.width as usize {
+                let point = top_left + Point::new(x as i32, y as i32);
+                self.set(point, image.is_black(x, y));
+            }
+        }
+    }
+    pub fn is_black(&self, point: Point) -> bool {
+        let (x, y) = (point.x as usize, point.y as usize);
+        self.rows[y * SCREEN_ROW_BYTES + x / 8] & (0x80 >> (x % 8)) != 0
+    }
+    fn set(&mut self, point: Point, black: bool) {
+        let (x, y) = (point.x as usize, point.y as usize);
+        let byte = &mut self.rows[y * SCREEN_ROW_BYTES + x / 8];
+        if black {
+            *byte |= 0x80 >> (x % 8);
+        } else {
+            *byte &= !(0x80 >> (x % 8));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bitmap(x: u32, y: u32, width: u32, height: u32, pixels: &[u8]) -> Bitmap {
+        Bitmap {
+            x,
+            y,
+            width,
+            height,
+            pixels: pixels.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rows_start_on_a_new_byte() {
+        // 10 pixels wide, the last 6 bits of every second byte are padding
+        let image = Image::decode(&bitmap(3, 2, 10, 2, &[0x80, 0x40, 0x00, 0xff])).unwrap();
+        let mut screen = Screen::default();
+        screen.blit(&image);
+        assert!(screen.is_black(Point::new(3, 2)));
+        assert!(!screen.is_black(Point::new(4, 2)));
+        assert!(screen.is_black(Point::new(12, 2)));
+        assert!(screen.is_black(Point::new(11, 3)));
+        assert!(screen.is_black(Point::new(12, 3)));
+        assert!(!screen.is_black(Point::new(13, 3)));
+        // white pixels of a later picture clear the earlier ones
+        screen.blit(&Image::decode(&bitmap(0, 0, 8, 3, &[0, 0, 0])).unwrap());
+        assert!(!screen.is_black(Point::new(3, 2)));
+        assert!(screen.is_black(Point::new(12, 3)));
+    }
+
+    #[test]
+    fn run_length_encoded_pixels() {
+        let encoded = Bitmap {
+            rle: true,
+            ..bitmap(0, 0, 16, 4, &[6, 0xff, 2, 0x0f])
+        };
+        let image = Image::decode(&encoded).unwrap();
+        assert_eq!(image.rows, [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x0f, 0x0f]);
+        let decode = |pixels: &[u8]| {
+            Image::decode(&Bitmap {
+                rle: true,
+                ..bitmap(0, 0, 16, 4, pixels)
+            })
+        };
+        assert_eq!(decode(&[6, 0xff, 2]), Err(BitmapError::InvalidRle));
+        assert_eq!(decode(&[0, 0xff, 8, 0]), Err(BitmapError::InvalidRle));
+        assert_eq!(decode(&[255, 0xff]), Err(BitmapError::InvalidRle));
+        assert_eq!(decode(&[7, 0xff]), Err(BitmapError::InvalidLength));
+    }
+
+    #[test]
+    fn bitmaps_fit_on_the_canvas() {
+        let decode = |x, y, width, height| {
+            let len = row_bytes(width) * height as usize;
+            Image::decode(&bitmap(x, y, width, height, &vec![0; len])).map(|_| ())
+        };
+        assert_eq!(decode(0, 0, CANVAS_WIDTH, CANVAS_HEIGHT), Ok(()));
+        assert_eq!(decode(0, 0, 0, 8), Err(BitmapError::Empty));
+        assert_eq!(decode(290, 0, 8, 8), Err(BitmapError::OutOfBounds));
+        assert_eq!(decode(0, 121, 8, 8), Err(BitmapError::OutOfBounds));
+        assert_eq!(decode(u32::MAX, 0, 8, 8), Err(BitmapError::OutOfBounds));
+        assert_eq!(
+            Image::decode(&bitmap(0, 0, 8, 2, &[0])),
+            Err(BitmapError::InvalidLength)
+        );
+    }
+}
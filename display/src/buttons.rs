@@ -0,0 +1,190 @@
+// debouncing and gesture detection for the push buttons. the firmware samples the pins and
+// feeds the levels in, so this runs on the host with made up timings as well
+use core::time::Duration;
+
+use crate::clock::Tick;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    Short,
+    Double,
+    // reported while the button is still held
+    Long,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ButtonConfig {
+    // a level has to be stable this long to count, the contacts bounce for a few ms
+    pub debounce: Duration,
+    pub long_press: Duration,
+    // a second press has to start within this after the first was released
+    pub double_press: Duration,
+}
+
+impl Default for ButtonConfig {
+    fn default() -> Self {
+        ButtonConfig {
+            debounce: Duration::from_millis(30),
+            long_press: Duration::from_millis(800),
+            double_press: Duration::from_millis(300),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Button {
+    config: ButtonConfig,
+    // the last sampled level and since when it is there
+    raw: bool,
+    raw_since: Option<Tick>,
+    // the debounced level
+    pressed: bool,
+    pressed_at: Option<Tick>,
+    long_reported: bool,
+    // release of a short press that could still become a double press
+    released_at: Option<Tick>,
+}
+
+impl Button {
+    pub fn new(config: ButtonConfig) -> Self {
+        Button {
+            config,
+            raw: false,
+            raw_since: None,
+            pressed: false,
+            pressed_at: None,
+            long_reported: false,
+            released_at: None,
+        }
+    }
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+    // pressed is the level of the pin, already inverted for active low buttons. a short press
+    // is only reported once the time for a second press has passed
+    pub fn update(&mut self, pressed: bool, now: Tick) -> Option<Gesture> {
+        if pressed != self.raw || self.raw_since.is_none() {
+            self.raw = pressed;
+            self.raw_since = Some(now);
+        }
+        let stable = self
+            .raw_since
+            .is_some_and(|since| now.duration_since(since) >= self.config.debounce);
+        if stable && self.raw != self.pressed {
+            self.pressed = self.raw;
+            return if self.pressed {
+                self.pressed_at = Some(now);
+                self.long_reported = false;
+                None
+            } else {
+                self.release(now)
+            };
+        }
+
+        if self.pressed {
+            let held = self
+                .pressed_at
+                .is_some_and(|at| now.duration_since(at) >= self.config.long_press);
+            if held && !self.long_reported {
+                self.long_reported = true;
+                self.released_at = None;
+                return Some(Gesture::Long);
+            }
+        } else if let Some(released) = self.released_at {
+            if now.duration_since(released) >= self.config.double_press {
+                self.released_at = None;
+                return Some(Gesture::Short);
+            }
+        }
+        None
+    }
+    fn release(&mut self, now: Tick) -> Option<Gesture> {
+        // the long press was already reported while holding
+        if self.long_reported {
+            return None;
+        }
+        match self.released_at.take() {
+            Some(_) => Some(Gesture::Double),
+            None => {
+                self.released_at = Some(now);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // feeds the levels as (ms since start, pressed) and collects the gestures, the pin is sampled
+    // every 5ms like on the firmware
+    fn run(edges: &[(u64, bool)], until_ms: u64) -> Vec<(u64, Gesture)> {
+        let start = Tick::default();
+        let mut button = Button::new(ButtonConfig::default());
+        let mut gestures = Vec::new();
+        let mut level = false;
+        for ms in (0..=until_ms).step_by(5) {
+            if let Some((_, pressed)) = edges.iter().find(|(at, _)| *at == ms) {
+                level = *pressed;
+            }
+            if let Some(gesture) = button.update(level, start + Duration::from_millis(ms)) {
+                gestures.push((ms, gesture));
+            }
+        }
+        gestures
+    }
+
+    #[test]
+    fn bouncing_short_press() {
+        let gestures = run(
+            &[
+                (100, true),
+                (105, false),
+                (110, true),
+                (200, false),
+                (205, true),
+                (210, false),
+            ],
+            1000,
+        );
+        assert_eq!(gestures.len(), 1);
+        assert_eq!(gestures[0].1, Gesture::Short);
+        // reported once the double press window has passed after the release
+        assert!(gestures[0].0 >= 540);
+    }
+
+    #[test]
+    fn short_glitches_are_ignored() {
+        assert!(run(&[(100, true), (115, false)], 1000).is_empty());
+    }
+
+    #[test]
+    fn double_press() {
+        let gestures = run(
+            &[(100, true), (200, false), (350, true), (450, false)],
+            1500,
+        );
+        assert_eq!(
+            gestures.iter().map(|g| g.1).collect::<Vec<_>>(),
+            [Gesture::Double]
+        );
+        // too slow for a double press
+        let gestures = run(
+            &[(100, true), (200, false), (700, true), (800, false)],
+            1500,
+        );
+        assert_eq!(
+            gestures.iter().map(|g| g.1).collect::<Vec<_>>(),
+            [Gesture::Short, Gesture::Short]
+        );
+    }
+
+    #[test]
+    fn long_press_is_reported_while_held() {
+        let gestures = run(&[(100, true), (2000, false)], 3000);
+        assert_eq!(gestures.len(), 1);
+        assert_eq!(gestures[0].1, Gesture::Long);
+        assert!(gestures[0].0 < 2000);
+    }
+}
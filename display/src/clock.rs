@@ -0,0 +1,46 @@
+// the time of the dashboard. the device hands in the milliseconds of a monotonic clock, like the
+// ticks since boot, so the state and the pages run without std
+use core::ops::Add;
+use core::time::Duration;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Tick(u64);
+
+impl Tick {
+    pub const fn from_millis(millis: u64) -> Self {
+        Tick(millis)
+    }
+    pub const fn millis(&self) -> u64 {
+        self.0
+    }
+    // zero if earlier is later, like for std::time::Instant
+    pub fn duration_since(&self, earlier: Tick) -> Duration {
+        Duration::from_millis(self.0.saturating_sub(earlier.0))
+    }
+    // for the simulator and the firmware, which count from when they started
+    #[cfg(feature = "std")]
+    pub fn since(start: std::time::Instant) -> Self {
+        Tick(start.elapsed().as_millis() as u64)
+    }
+}
+
+impl Add<Duration> for Tick {
+    type Output = Tick;
+    fn add(self, duration: Duration) -> Tick {
+        Tick(self.0.saturating_add(duration.as_millis() as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_and_durations() {
+        let start = Tick::from_millis(1500);
+        let later = start + Duration::from_secs(90);
+        assert_eq!(later.millis(), 91_500);
+        assert_eq!(later.duration_since(start), Duration::from_secs(90));
+        assert_eq!(start.duration_since(later), Duration::ZERO);
+    }
+}
@@ -0,0 +1,38 @@
+// the colours the pages are drawn with. black and white panels draw the accent in black, the
+// red/black/white ones in red
+use embedded_graphics::pixelcolor::{BinaryColor, Rgb565};
+use embedded_graphics::prelude::{PixelColor, RgbColor};
+use epd_waveshare::color::{Color, TriColor};
+
+pub trait DisplayColor: PixelColor {
+    const BLACK: Self;
+    const WHITE: Self;
+    // grid import, discharging and alerts
+    const ACCENT: Self;
+}
+
+impl DisplayColor for Color {
+    const BLACK: Self = Color::Black;
+    const WHITE: Self = Color::White;
+    const ACCENT: Self = Color::Black;
+}
+
+impl DisplayColor for TriColor {
+    const BLACK: Self = TriColor::Black;
+    const WHITE: Self = TriColor::White;
+    const ACCENT: Self = TriColor::Chromatic;
+}
+
+// monochrome oleds, the pages are drawn with the lit pixels like the ink of the epd
+impl DisplayColor for BinaryColor {
+    const BLACK: Self = BinaryColor::On;
+    const WHITE: Self = BinaryColor::Off;
+    const ACCENT: Self = BinaryColor::On;
+}
+
+// colour tfts, the pages stay black on white
+impl DisplayColor for Rgb565 {
+    const BLACK: Self = <Rgb565 as RgbColor>::BLACK;
+    const WHITE: Self = <Rgb565 as RgbColor>::WHITE;
+    const ACCENT: Self = Rgb565::RED;
+}
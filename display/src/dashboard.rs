@@ -0,0 +1,567 @@
+// everything the pages show, merged from the messages of the server. the server only sends the
+// fields that changed, so missing fields keep their previous values
+
+use alloc::string::String;
+use embedded_graphics::prelude::Size;
+
+use crate::alerts::{self, AlertEngine};
+use crate::battery::BatteryStatus;
+use crate::bitmap::{Image, Screen};
+use crate::clock::Tick;
+use crate::history::{self, ActualSeries};
+use crate::layout::PageLayout;
+use crate::metrics::EnergyBalance;
+use crate::night::NightMode;
+use crate::notice::{self, Notices};
+use crate::panel::{CANVAS_HEIGHT, CANVAS_WIDTH};
+use crate::phases::{PhaseError, PhaseSummary};
+use crate::prediction::{self, PredictionError, CHART_COLUMNS};
+use crate::price::PriceCache;
+use crate::prototypes::types::data::Oneof;
+use crate::prototypes::types::{
+    BatteryData, DailyNew, Data, HourlyNew, NewUiStruct, Notice, PhaseData, TotalDataNew,
+    WallboxNew, WeatherNew,
+};
+use crate::system::SystemInfo;
+use crate::tariff::{CostCalculator, Tariff};
+use crate::TotalsMode;
+
+// what an update changed, so the pages only redraw the affected parts
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Changes {
+    // the values of the power flow, the status strip and the self sufficiency
+    pub flow: bool,
+    pub sun: bool,
+    pub weather: bool,
+    pub totals: bool,
+    // a new bucket of the actual generation or a new prediction
+    pub chart: bool,
+    // a new forecast or a new hour
+    pub prices: bool,
+    // the last prices ran out after midnight
+    pub prices_cleared: bool,
+    pub phases: bool,
+    pub battery: bool,
+    pub connection: bool,
+    // an alert started or cleared
+    pub alert: bool,
+    // the night started or ended, the summary replaces the pages in between
+    pub night: bool,
+    // a new layout of the dashboard, the clients build the page from it
+    pub layout: bool,
+    // a picture from the server, the screen page is shown
+    pub bitmap: bool,
+    // the server asked for a full refresh of the panel
+    pub full_refresh: bool,
+    // a notice came, expired or was closed
+    pub notice: bool,
+    // new alert rules from the server, the firmware stores them
+    pub alert_rules: bool,
+    // label of data that was invalid, it is either dropped or shown instead
+    pub invalid: Option<&'static str>,
+}
+
+impl Changes {
+    // used to draw a page from scratch
+    pub fn all() -> Self {
+        Changes {
+            flow: true,
+            sun: true,
+            weather: true,
+            totals: true,
+            chart: true,
+            prices: true,
+            prices_cleared: false,
+            phases: true,
+            battery: true,
+            connection: true,
+            alert: true,
+            night: true,
+            layout: true,
+            bitmap: true,
+            full_refresh: false,
+            notice: true,
+            alert_rules: false,
+            invalid: None,
+        }
+    }
+}
+
+pub struct DashboardState {
+    // latest value of every field
+    pub ui: NewUiStruct,
+    pub prediction: Result<[f32; CHART_COLUMNS], PredictionError>,
+    pub actual: ActualSeries,
+    pub balance: EnergyBalance,
+    pub costs: CostCalculator,
+    pub prices: PriceCache,
+    pub phases: Option<Result<PhaseSummary, PhaseError>>,
+    pub battery: BatteryStatus,
+    pub totals_mode: TotalsMode,
+    pub connected: bool,
+    // filled in by the client, only the age of the last message is tracked here
+    pub system: SystemInfo,
+    pub alerts: AlertEngine,
+    pub night: NightMode,
+    // the layout sent by the server, the clients fall back to their own until then
+    pub layout: Option<PageLayout>,
+    // of the panel the layouts of the server are for, set by the client
+    pub panel_size: Size,
+    // the pictures of the server, there is none until the first one comes
+    pub screen: Option<Screen>,
+    pub notices: Notices,
+    // the time of the last message, tick or button, the pages draw the ages and the night
+    // summary with it
+    pub now: Tick,
+    last_sample: Option<Tick>,
+}
+
+impl Default for DashboardState {
+    fn default() -> Self {
+        DashboardState::new(Tariff::default(), TotalsMode::default())
+    }
+}
+
+impl DashboardState {
+    pub fn new(tariff: Tariff, totals_mode: TotalsMode) -> Self {
+        DashboardState {
+            ui: NewUiStruct::default(),
+            prediction: Ok([0.0; CHART_COLUMNS]),
+            actual: ActualSeries::new(),
+            balance: EnergyBalance::new(),
+            costs: CostCalculator::new(tariff),
+            prices: PriceCache::new(),
+            phases: None,
+            battery: BatteryStatus::default(),
+            totals_mode,
+            connected: false,
+            system: SystemInfo::new(Tick::default()),
+            alerts: AlertEngine::default(),
+            night: NightMode::default(),
+            layout: None,
+            panel_size: Size::new(CANVAS_WIDTH, CANVAS_HEIGHT),
+            screen: None,
+            notices: Notices::default(),
+            now: Tick::default(),
+            last_sample: None,
+        }
+    }
+    pub fn apply(&mut self, data: &Data, now: Tick) -> Changes {
+        self.now = now;
+        self.system.last_message = Some(now);
+        let mut changes = match &data.oneof {
+            Some(Oneof::UiData(ui_data)) => self.apply_ui(ui_data, now),
+            Some(Oneof::Prediction(prediction)) => {
+                // the prediction can come in any resolution, it gets resampled to the width of
+                // the chart. invalid predictions are shown instead of the chart
+                self.prediction = prediction::resample(prediction);
+                Changes {
+                    chart: true,
+                    invalid: self.prediction.err().map(|e| e.label()),
+                    ..Default::default()
+                }
+            }
+            Some(Oneof::PriceForecast(forecast)) => match self.prices.update(forecast) {
+                Ok(_) => Changes {
+                    prices: true,
+                    ..Default::default()
+                },
+                // the last valid forecast is kept
+                Err(e) => Changes {
+                    invalid: Some(e.label()),
+                    ..Default::default()
+                },
+            },
+            Some(Oneof::Layout(layout)) => {
+                match PageLayout::parse_for(&layout.config, self.panel_size) {
+                    Ok(layout) => {
+                        self.layout = Some(layout);
+                        Changes {
+                            layout: true,
+                            ..Default::default()
+                        }
+                    }
+                    // the current layout is kept
+                    Err(e) => Changes {
+                        invalid: Some(e.label()),
+                        ..Default::default()
+                    },
+                }
+            }
+            Some(Oneof::Bitmap(bitmap)) => match Image::decode(bitmap) {
+                Ok(image) => {
+                    self.screen.get_or_insert_with(Screen::default).blit(&image);
+                    Changes {
+                        bitmap: true,
+                        full_refresh: image.full_refresh,
+                        ..Default::default()
+                    }
+                }
+                // nothing of a broken picture is drawn
+                Err(e) => Changes {
+                    invalid: Some(e.label()),
+                    ..Default::default()
+                },
+            },
+            Some(Oneof::Notice(notice)) => self.show_notice(notice, now),
+            Some(Oneof::AlertRules(rules)) => match alerts::rules_or_default(&rules.rules) {
+                // the banner of the old rules goes away
+                Ok(rules) => {
+                    let active = self.alerts.active().is_some();
+                    self.alerts.set_rules(rules);
+                    Changes {
+                        alert: active,
+                        alert_rules: true,
+                        ..Default::default()
+                    }
+                }
+                // the current rules are kept
+                Err(e) => Changes {
+                    invalid: Some(e.label()),
+                    ..Default::default()
+                },
+            },
+            None => Changes::default(),
+        };
+        changes.alert |= self.evaluate_alerts(now);
+        changes
+    }
+    // plain text frames of the server, they are shown as a notice
+    pub fn show_text(&mut self, text: &str, now: Tick) -> Changes {
+        self.now = now;
+        self.show_notice(&notice::from_text(text), now)
+    }
+    // the alerts also change without messages, like when the server goes quiet. the night
+    // only starts and ends here, the clock keeps running between the messages. notices expire
+    pub fn tick(&mut self, now: Tick) -> Changes {
+        self.now = now;
+        Changes {
+            alert: self.evaluate_alerts(now),
+            night: self.night.update(now),
+            notice: self.notices.update(now),
+            ..Default::default()
+        }
+    }
+    // a button was pressed, at night the pages come back for a while and a notice is closed
+    pub fn wake(&mut self, now: Tick) -> Changes {
+        self.now = now;
+        Changes {
+            night: self.night.wake(now),
+            notice: self.notices.dismiss(),
+            ..Default::default()
+        }
+    }
+    fn show_notice(&mut self, notice: &Notice, now: Tick) -> Changes {
+        match self.notices.show(notice, now) {
+            Ok(_) => Changes {
+                notice: true,
+                ..Default::default()
+            },
+            Err(e) => Changes {
+                invalid: Some(e.label()),
+                ..Default::default()
+            },
+        }
+    }
+    fn evaluate_alerts(&mut self, now: Tick) -> bool {
+        self.alerts
+            .evaluate(&self.ui, self.system.last_message, now)
+    }
+    fn apply_ui(&mut self, ui_data: &NewUiStruct, now: Tick) -> Changes {
+        let mut changes = Changes {
+            flow: true,
+            ..Default::default()
+        };
+        let ui = &mut self.ui;
+        merge(&mut ui.ts, &ui_data.ts);
+        merge(&mut ui.stat_state, &ui_data.stat_state);
+        merge(&mut ui.gui_bat_data_power, &ui_data.gui_bat_data_power);
+        merge(&mut ui.gui_inverter_power, &ui_data.gui_inverter_power);
+        merge(&mut ui.gui_house_pow, &ui_data.gui_house_pow);
+        merge(&mut ui.gui_grid_pow, &ui_data.gui_grid_pow);
+        merge(
+            &mut ui.gui_bat_data_fuel_charge,
+            &ui_data.gui_bat_data_fuel_charge,
+        );
+        merge(&mut ui.gui_charging_info, &ui_data.gui_charging_info);
+        merge(&mut ui.gui_boosting_info, &ui_data.gui_boosting_info);
+        merge(&mut ui.wallbox, &ui_data.wallbox);
+        merge(&mut ui.battery, &ui_data.battery);
+        if let Some(weather) = &ui_data.weather {
+            // daily and hourly weather can come on their own
+            let merged = ui.weather.get_or_insert_with(WeatherNew::default);
+            changes.sun = merge(&mut merged.daily, &weather.daily);
+            changes.weather = merge(&mut merged.hourly, &weather.hourly);
+            if let Some(daily) = &weather.daily {
+                if let (Some(sunrise), Some(sunset)) = (daily.sunrise.first(), daily.sunset.first())
+                {
+                    self.night.set_sun(sunrise, sunset);
+                }
+            }
+        }
+        if let Some(total_data) = &ui_data.total_data {
+            changes.totals = total_data.new;
+            merge(&mut ui.total_data, &ui_data.total_data);
+            self.balance
+                .set_totals(&total_data.consumption, &total_data.generated);
+        }
+        if let Some(phase_data) = &ui_data.phases {
+            merge(&mut ui.phases, &ui_data.phases);
+            self.phases = Some(PhaseSummary::from_data(phase_data));
+            changes.phases = true;
+        }
+        self.battery.update(ui_data);
+        changes.battery = ui_data.battery.is_some()
+            || ui_data.gui_bat_data_fuel_charge.is_some()
+            || ui_data.gui_bat_data_power.is_some();
+
+        let minute = self.ui.ts.as_deref().and_then(history::parse_minute_of_day);
+        // only a new ts sets the clock, an old one would turn it back
+        if let (Some(minute), Some(_)) = (minute, &ui_data.ts) {
+            self.night.set_clock(minute, now);
+        }
+        let grid_kw = parse_kw(&self.ui.gui_grid_pow);
+        let house_kw = parse_kw(&self.ui.gui_house_pow);
+        let inverter_kw = parse_kw(&self.ui.gui_inverter_power);
+
+        // after midnight tomorrows prices become today, the strip is redrawn every hour
+        if let Some(minute) = minute {
+            let had_prices = !self.prices.is_empty();
+            changes.prices = self.prices.tick(minute) && !self.prices.is_empty();
+            changes.prices_cleared = had_prices && self.prices.is_empty();
+        }
+
+        // the grid flows are integrated over the time between two messages
+        let elapsed = match self.last_sample {
+            Some(last) => now.duration_since(last).as_secs_f32(),
+            None => 0.0,
+        };
+        self.last_sample = Some(now);
+        if let (Some(minute), Some(grid_kw)) = (minute, grid_kw) {
+            self.balance.record_grid(minute, grid_kw, elapsed);
+            if let Some(house_kw) = house_kw {
+                self.costs.record(minute, grid_kw, house_kw, elapsed);
+            }
+        }
+
+        // keeping track of the actual generation, the chart only needs to be redrawn once a
+        // new 5 minute bucket starts
+        if let (Some(minute), Some(inverter_kw)) = (minute, inverter_kw) {
+            changes.chart = self.actual.record(minute, inverter_kw);
+        }
+        changes
+    }
+}
+
+// returns true if there was a new value. the strings and vecs of the last value are reused, so
+// the messages of the steady state don't allocate here
+fn merge<T: Reuse + Default>(target: &mut Option<T>, update: &Option<T>) -> bool {
+    match update {
+        Some(value) => {
+            target.get_or_insert_with(T::default).reuse_from(value);
+            true
+        }
+        None => false,
+    }
+}
+
+// clone_from for the messages, the derived one of prost drops the old strings and clones new
+// ones
+trait Reuse {
+    fn reuse_from(&mut self, other: &Self);
+}
+
+impl Reuse for String {
+    fn reuse_from(&mut self, other: &Self) {
+        self.clone_from(other);
+    }
+}
+
+// the pattern fails to compile once a message gets a field that is not listed
+macro_rules! reuse_fields {
+    ($($message:ident { $($field:ident),* })*) => {
+        $(impl Reuse for $message {
+            fn reuse_from(&mut self, other: &Self) {
+                let $message { $($field: _),* } = other;
+                $(self.$field.clone_from(&other.$field);)*
+            }
+        })*
+    };
+}
+
+reuse_fields! {
+    HourlyNew { time, temperature_2m, rain, showers, cloud_cover, uv_index, uv_index_clear_sky }
+    DailyNew { time, sunset, sunrise }
+    TotalDataNew { consumption, generated, new }
+    WallboxNew { present, state, charging_power, session_energy, car_connected }
+    PhaseData { grid_power, grid_voltage, inverter_power, grid_frequency }
+    BatteryData {
+        voltage,
+        current,
+        temperature,
+        cycles,
+        state_of_health,
+        min_cell_voltage,
+        max_cell_voltage,
+        capacity_kwh
+    }
+}
+
+fn parse_kw(value: &Option<String>) -> Option<f32> {
+    value.as_deref()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::LayoutError;
+    use crate::prototypes::types::{DailyNew, Layout, Prediction};
+    use std::time::Duration;
+
+    fn ui(ui_data: NewUiStruct) -> Data {
+        Data {
+            oneof: Some(Oneof::UiData(ui_data)),
+        }
+    }
+
+    #[test]
+    fn missing_fields_keep_their_values() {
+        let mut state = DashboardState::default();
+        let now = Tick::default();
+        state.apply(
+            &ui(NewUiStruct {
+                ts: Some("10:00".to_string()),
+                gui_grid_pow: Some("1.50".to_string()),
+                ..Default::default()
+            }),
+            now,
+        );
+        state.apply(
+            &ui(NewUiStruct {
+                gui_house_pow: Some("0.80".to_string()),
+                ..Default::default()
+            }),
+            now,
+        );
+        assert_eq!(state.ui.ts.as_deref(), Some("10:00"));
+        assert_eq!(state.ui.gui_grid_pow.as_deref(), Some("1.50"));
+        assert_eq!(state.ui.gui_house_pow.as_deref(), Some("0.80"));
+    }
+
+    #[test]
+    fn weather_is_merged() {
+        let mut state = DashboardState::default();
+        let changes = state.apply(
+            &ui(NewUiStruct {
+                weather: Some(WeatherNew {
+                    daily: Some(DailyNew::default()),
+                    hourly: None,
+                }),
+                ..Default::default()
+            }),
+            Tick::default(),
+        );
+        assert!(changes.sun);
+        assert!(!changes.weather);
+        state.apply(&ui(NewUiStruct::default()), Tick::default());
+        assert!(state.ui.weather.unwrap().daily.is_some());
+    }
+
+    #[test]
+    fn invalid_predictions_are_kept_as_error() {
+        let mut state = DashboardState::default();
+        let changes = state.apply(
+            &Data {
+                oneof: Some(Oneof::Prediction(Prediction::default())),
+            },
+            Tick::default(),
+        );
+        assert!(changes.chart);
+        assert_eq!(changes.invalid, Some(PredictionError::Empty.label()));
+        assert_eq!(state.prediction, Err(PredictionError::Empty));
+    }
+
+    #[test]
+    fn alerts_follow_the_merged_data() {
+        let mut state = DashboardState::default();
+        let now = Tick::default();
+        let changes = state.apply(
+            &ui(NewUiStruct {
+                gui_bat_data_fuel_charge: Some("5.0".to_string()),
+                ..Default::default()
+            }),
+            now,
+        );
+        assert!(changes.alert);
+        assert!(!state.apply(&ui(NewUiStruct::default()), now).alert);
+        assert_eq!(state.alerts.active().as_deref(), Some("Battery below 10%"));
+        assert!(state.tick(now + Duration::from_secs(15 * 60)).alert);
+        assert_eq!(state.alerts.active().as_deref(), Some("No data for 15m"));
+    }
+
+    #[test]
+    fn alert_rules_from_the_server() {
+        use crate::prototypes::types::AlertRules;
+
+        let rules = |rules: &str| Data {
+            oneof: Some(Oneof::AlertRules(AlertRules {
+                rules: rules.to_string(),
+            })),
+        };
+        let mut state = DashboardState::default();
+        let now = Tick::default();
+        state.apply(
+            &ui(NewUiStruct {
+                gui_bat_data_fuel_charge: Some("5.0".to_string()),
+                ..Default::default()
+            }),
+            now,
+        );
+        // the banner of the default rules goes away
+        let changes = state.apply(&rules("battery<3"), now);
+        assert!(changes.alert_rules);
+        assert!(changes.alert);
+        assert_eq!(state.alerts.active(), None);
+        assert!(state.apply(&rules("battery<10, error"), now).alert);
+        assert_eq!(state.alerts.active().as_deref(), Some("Battery below 10%"));
+        // broken rules keep the current ones
+        let changes = state.apply(&rules("battery<x"), now);
+        assert!(!changes.alert_rules);
+        assert_eq!(changes.invalid, Some("invalid alert value"));
+        assert_eq!(state.alerts.rules().count(), 2);
+        // and without rules the built-in ones come back
+        state.apply(&rules(""), now);
+        assert_eq!(state.alerts.rules().count(), alerts::default_rules().len());
+    }
+
+    #[test]
+    fn chart_changes_with_a_new_bucket() {
+        let mut state = DashboardState::default();
+        let at = |ts: &str| {
+            ui(NewUiStruct {
+                ts: Some(ts.to_string()),
+                gui_inverter_power: Some("2.00".to_string()),
+                ..Default::default()
+            })
+        };
+        assert!(state.apply(&at("10:00"), Tick::default()).chart);
+        assert!(!state.apply(&at("10:01"), Tick::default()).chart);
+        assert!(state.apply(&at("10:05"), Tick::default()).chart);
+    }
+
+    #[test]
+    fn invalid_layouts_keep_the_last_one() {
+        let mut state = DashboardState::default();
+        let layout = |config: &str| Data {
+            oneof: Some(Oneof::Layout(Layout {
+                config: config.to_string(),
+            })),
+        };
+        let valid = "[[widget]]\ntype = \"chart\"\nregion = [0, 0, 147, 56]";
+        assert!(state.apply(&layout(valid), Tick::default()).layout);
+        let changes = state.apply(&layout("[[widget]]"), Tick::default());
+        assert!(!changes.layout);
+        assert_eq!(changes.invalid, Some(LayoutError::MissingType.label()));
+        assert_eq!(state.layout.unwrap().widgets.len(), 1);
+    }
+}
@@ -0,0 +1,68 @@
+// the errors of the drawing code. the callers can tell bad data, which the next message may
+// fix, from a display that failed
+use core::fmt::{Debug, Write};
+
+use crate::{DrawError, TextBuffer};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DisplayError {
+    // a value that does not fit where it is drawn, like a percentage with 4 digits
+    InvalidInput,
+    // a text did not fit into its buffer, see text!
+    TextOverflow,
+    // there is nothing to draw, like a chart without values
+    MissingData,
+    // the display failed. the error of the display is kept as its Debug text, so DisplayError
+    // stays the same for every display. the epd buffers and the simulator can not fail at all,
+    // whoever needs the error of an oled or tft itself gets it as DrawError by drawing on the
+    // DisplayBoxed directly
+    Draw(TextBuffer),
+}
+
+impl DisplayError {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DisplayError::InvalidInput => "invalid input",
+            DisplayError::TextOverflow => "text too long",
+            DisplayError::MissingData => "missing data",
+            DisplayError::Draw(_) => "error drawing on the display",
+        }
+    }
+    // only the display itself failing is not down to the data
+    pub fn is_recoverable(&self) -> bool {
+        !matches!(self, DisplayError::Draw(_))
+    }
+}
+
+impl core::fmt::Display for DisplayError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DisplayError::Draw(err) => write!(f, "{}: {}", self.label(), err),
+            _ => f.write_str(self.label()),
+        }
+    }
+}
+
+impl core::error::Error for DisplayError {}
+
+// keeps the Debug text of the error of the display, cut where the buffer ends
+impl<E: Debug> From<DrawError<E>> for DisplayError {
+    fn from(err: DrawError<E>) -> Self {
+        let mut text = Cut(TextBuffer::new());
+        let _ = write!(text, "{:?}", err.0);
+        DisplayError::Draw(text.0)
+    }
+}
+
+struct Cut(TextBuffer);
+
+impl Write for Cut {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for c in s.chars() {
+            self.0.push(c).map_err(|_| core::fmt::Error)?;
+        }
+        Ok(())
+    }
+}
+
+pub type Result<T, E = DisplayError> = core::result::Result<T, E>;
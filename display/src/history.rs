@@ -0,0 +1,189 @@
+// keeps the actual generation of the current day in memory, aligned to the same 5 minute
+// buckets the prediction uses, so both curves can be drawn on top of each other
+
+pub const BUCKET_MINUTES: u16 = 5;
+pub const BUCKETS_PER_DAY: usize = 288;
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+pub struct ActualSeries {
+    sums: [f32; BUCKETS_PER_DAY],
+    counts: [u16; BUCKETS_PER_DAY],
+    last_minute: Option<u16>,
+}
+
+impl Default for ActualSeries {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ActualSeries {
+    pub const fn new() -> Self {
+        ActualSeries {
+            sums: [0.0; BUCKETS_PER_DAY],
+            counts: [0; BUCKETS_PER_DAY],
+            last_minute: None,
+        }
+    }
+    pub fn reset(&mut self) {
+        self.sums = [0.0; BUCKETS_PER_DAY];
+        self.counts = [0; BUCKETS_PER_DAY];
+        self.last_minute = None;
+    }
+    // records a sample of the inverter power in kW. returns true if the sample started a new
+    // bucket, which is when the chart needs to be redrawn
+    pub fn record(&mut self, minute_of_day: u16, kw: f32) -> bool {
+        if minute_of_day >= MINUTES_PER_DAY || !kw.is_finite() {
+            return false;
+        }
+        if let Some(last) = self.last_minute {
+            // the clock jumped back by more than an hour, so a new day has started
+            if minute_of_day + 60 < last {
+                self.reset();
+            }
+        }
+        let bucket = (minute_of_day / BUCKET_MINUTES) as usize;
+        let new_bucket = match self.last_minute {
+            Some(last) => (last / BUCKET_MINUTES) as usize != bucket,
+            None => true,
+        };
+        self.sums[bucket] += kw.max(0.0);
+        self.counts[bucket] += 1;
+        self.last_minute = Some(minute_of_day);
+        new_bucket
+    }
+    // average power of the bucket, none if nothing was recorded in it
+    pub fn get(&self, bucket: usize) -> Option<f32> {
+        match self.counts.get(bucket) {
+            Some(0) | None => None,
+            Some(count) => Some(self.sums[bucket] / *count as f32),
+        }
+    }
+    // average over a range of buckets, only taking the buckets into account that have data
+    pub fn average(&self, buckets: core::ops::Range<usize>) -> Option<f32> {
+        let (sum, count) = buckets
+            .filter_map(|bucket| self.get(bucket))
+            .fold((0.0, 0), |(sum, count), v| (sum + v, count + 1));
+        if count == 0 {
+            return None;
+        }
+        Some(sum / count as f32)
+    }
+    pub fn max(&self) -> Option<f32> {
+        (0..BUCKETS_PER_DAY)
+            .filter_map(|bucket| self.get(bucket))
+            .fold(None, |max, v| match max {
+                None => Some(v),
+                Some(m) => Some(v.max(m)),
+            })
+    }
+    pub fn now_minute(&self) -> Option<u16> {
+        self.last_minute
+    }
+}
+
+// the server sends the time either as "14:05" or as "2:05PM"
+pub fn parse_minute_of_day(ts: &str) -> Option<u16> {
+    let ts = ts.trim();
+    let (time, pm) = if let Some(stripped) = ts.strip_suffix("PM") {
+        (stripped.trim_end(), Some(true))
+    } else if let Some(stripped) = ts.strip_suffix("AM") {
+        (stripped.trim_end(), Some(false))
+    } else {
+        (ts, None)
+    };
+    let (hours, minutes) = time.split_once(':')?;
+    let mut hours: u16 = hours.parse().ok()?;
+    let minutes: u16 = minutes.get(..2).unwrap_or(minutes).parse().ok()?;
+    if minutes >= 60 {
+        return None;
+    }
+    match pm {
+        Some(pm) => {
+            if hours == 0 || hours > 12 {
+                return None;
+            }
+            hours %= 12;
+            if pm {
+                hours += 12;
+            }
+        }
+        None => {
+            if hours >= 24 {
+                return None;
+            }
+        }
+    }
+    Some(hours * 60 + minutes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_across_the_day() {
+        let mut series = ActualSeries::new();
+        assert!(series.record(6 * 60, 0.5));
+        assert!(!series.record(6 * 60 + 4, 1.5));
+        assert!(series.record(6 * 60 + 5, 2.0));
+        assert!(series.record(23 * 60 + 59, 0.1));
+        assert_eq!(series.get(72), Some(1.0));
+        assert_eq!(series.get(73), Some(2.0));
+        assert_eq!(series.get(BUCKETS_PER_DAY - 1), Some(0.1));
+        assert_eq!(series.get(0), None);
+        assert_eq!(series.get(BUCKETS_PER_DAY), None);
+        assert_eq!(series.average(72..74), Some(1.5));
+        assert_eq!(series.average(0..72), None);
+        assert_eq!(series.max(), Some(2.0));
+        assert_eq!(series.now_minute(), Some(23 * 60 + 59));
+        // negative power is the own consumption of the inverter, not generation
+        series.record(23 * 60 + 59, -1.0);
+        assert_eq!(series.get(BUCKETS_PER_DAY - 1), Some(0.05));
+    }
+
+    #[test]
+    fn duplicate_and_out_of_order_minutes() {
+        let mut series = ActualSeries::new();
+        series.record(600, 1.0);
+        // the same minute again is averaged into the bucket
+        assert!(!series.record(600, 3.0));
+        assert_eq!(series.get(120), Some(2.0));
+        // a late message goes into its own bucket without starting a new day
+        assert!(series.record(590, 4.0));
+        assert_eq!(series.get(118), Some(4.0));
+        assert_eq!(series.get(120), Some(2.0));
+        assert_eq!(series.now_minute(), Some(590));
+        // invalid samples are dropped
+        assert!(!series.record(MINUTES_PER_DAY, 1.0));
+        assert!(!series.record(610, f32::NAN));
+        assert_eq!(series.get(122), None);
+    }
+
+    #[test]
+    fn a_new_day_starts_over() {
+        let mut series = ActualSeries::new();
+        series.record(23 * 60 + 55, 0.2);
+        series.record(12 * 60, 3.0);
+        assert!(series.record(5, 0.1));
+        assert_eq!(series.get(BUCKETS_PER_DAY - 1), None);
+        assert_eq!(series.get(144), None);
+        assert_eq!(series.get(1), Some(0.1));
+        assert_eq!(series.now_minute(), Some(5));
+    }
+
+    #[test]
+    fn parses_the_times_of_the_server() {
+        assert_eq!(parse_minute_of_day("14:05"), Some(14 * 60 + 5));
+        assert_eq!(parse_minute_of_day(" 0:00 "), Some(0));
+        assert_eq!(parse_minute_of_day("14:05:59"), Some(14 * 60 + 5));
+        assert_eq!(parse_minute_of_day("2:05PM"), Some(14 * 60 + 5));
+        assert_eq!(parse_minute_of_day("12:30 AM"), Some(30));
+        assert_eq!(parse_minute_of_day("12:30PM"), Some(12 * 60 + 30));
+        for ts in [
+            "", "1405", "ab:cd", "24:00", "12:60", "0:05AM", "13:00PM", "-1:00",
+        ] {
+            assert_eq!(parse_minute_of_day(ts), None, "{}", ts);
+        }
+    }
+}
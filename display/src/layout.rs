@@ -0,0 +1,461 @@
+// the dashboard as a layout file, so the widgets can be rearranged without flashing. it is a
+// small subset of toml, one table per widget:
+//
+//   [[widget]]
+//   type = "chart"
+//   region = [0, 64, 147, 56]
+//   gridlines = true
+//
+// the region is x, y, width and height on the panel. a widget keeps its size and is drawn in
+// the top left corner of its region, so the region has to be at least that big. the regions
+// must not overlap or reach past the panel. besides the parts of the dashboard there are the
+// whole dashboard and the detail pages, for the larger panels
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::prelude::{OriginDimensions, Point, Size};
+use embedded_graphics::primitives::Rectangle;
+
+use crate::color::DisplayColor;
+use crate::pages::WidgetPage;
+use crate::panel::{self, PanelSpec, CANVAS_HEIGHT, CANVAS_WIDTH};
+use crate::widgets::{
+    AutarkyWidget, BatteryPageWidget, BatteryWidget, ChartWidget, DashboardWidget, FlowWidget,
+    PhasePageWidget, StatusWidget, SunWidget, SystemPageWidget, TotalsWidget, WeatherWidget,
+    Widget,
+};
+use crate::ChartOptions;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutError {
+    InvalidLine,
+    UnknownKey,
+    InvalidValue,
+    UnknownWidget,
+    MissingType,
+    MissingRegion,
+    NoWidgets,
+    RegionTooSmall,
+    OutOfBounds,
+    Overlap,
+}
+
+impl LayoutError {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LayoutError::InvalidLine => "invalid line in layout",
+            LayoutError::UnknownKey => "unknown key in layout",
+            LayoutError::InvalidValue => "invalid value in layout",
+            LayoutError::UnknownWidget => "unknown widget in layout",
+            LayoutError::MissingType => "widget without type",
+            LayoutError::MissingRegion => "widget without region",
+            LayoutError::NoWidgets => "layout without widgets",
+            LayoutError::RegionTooSmall => "region too small for widget",
+            LayoutError::OutOfBounds => "region outside of the canvas",
+            LayoutError::Overlap => "overlapping regions",
+        }
+    }
+}
+
+impl core::fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl core::error::Error for LayoutError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WidgetKind {
+    Flow,
+    Battery,
+    Status,
+    Autarky,
+    Totals,
+    Sun,
+    Weather,
+    Chart,
+    Dashboard,
+    PhasePage,
+    BatteryPage,
+    SystemPage,
+}
+
+impl WidgetKind {
+    pub const ALL: [WidgetKind; 12] = [
+        WidgetKind::Flow,
+        WidgetKind::Battery,
+        WidgetKind::Status,
+        WidgetKind::Autarky,
+        WidgetKind::Totals,
+        WidgetKind::Sun,
+        WidgetKind::Weather,
+        WidgetKind::Chart,
+        WidgetKind::Dashboard,
+        WidgetKind::PhasePage,
+        WidgetKind::BatteryPage,
+        WidgetKind::SystemPage,
+    ];
+    // the same as the names of the widgets
+    pub fn name(&self) -> &'static str {
+        match self {
+            WidgetKind::Flow => "flow",
+            WidgetKind::Battery => "battery",
+            WidgetKind::Status => "status",
+            WidgetKind::Autarky => "autarky",
+            WidgetKind::Totals => "totals",
+            WidgetKind::Sun => "sun",
+            WidgetKind::Weather => "weather",
+            WidgetKind::Chart => "chart",
+            WidgetKind::Dashboard => "dashboard",
+            WidgetKind::PhasePage => "phase_page",
+            WidgetKind::BatteryPage => "battery_page",
+            WidgetKind::SystemPage => "system_page",
+        }
+    }
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+    pub fn size(&self) -> Size {
+        match self {
+            WidgetKind::Flow => FlowWidget::BOUNDS.size,
+            WidgetKind::Battery => BatteryWidget::BOUNDS.size,
+            WidgetKind::Status => StatusWidget::BOUNDS.size,
+            WidgetKind::Autarky => AutarkyWidget::BOUNDS.size,
+            WidgetKind::Totals => TotalsWidget::BOUNDS.size,
+            WidgetKind::Sun => SunWidget::BOUNDS.size,
+            WidgetKind::Weather => WeatherWidget::BOUNDS.size,
+            WidgetKind::Chart => ChartWidget::BOUNDS.size,
+            WidgetKind::Dashboard => DashboardWidget::BOUNDS.size,
+            WidgetKind::PhasePage => PhasePageWidget::BOUNDS.size,
+            WidgetKind::BatteryPage => BatteryPageWidget::BOUNDS.size,
+            WidgetKind::SystemPage => SystemPageWidget::BOUNDS.size,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WidgetSpec {
+    pub kind: WidgetKind,
+    pub region: Rectangle,
+    // only the chart has options
+    pub options: ChartOptions,
+}
+
+impl WidgetSpec {
+    // origin is where the pages are on the panel, the widgets are drawn relative to them
+    pub fn widget<T>(&self, origin: Point) -> Box<dyn Widget<T>>
+    where
+        T: DrawTarget + OriginDimensions,
+        T::Color: DisplayColor,
+        T::Error: Debug,
+    {
+        let top_left = self.region.top_left - origin;
+        match self.kind {
+            WidgetKind::Flow => Box::new(FlowWidget::default().at(top_left)),
+            WidgetKind::Battery => Box::new(BatteryWidget::default().at(top_left)),
+            WidgetKind::Status => Box::new(StatusWidget::default().at(top_left)),
+            WidgetKind::Autarky => Box::new(AutarkyWidget::default().at(top_left)),
+            WidgetKind::Totals => Box::new(TotalsWidget::default().at(top_left)),
+            WidgetKind::Sun => Box::new(SunWidget::default().at(top_left)),
+            WidgetKind::Weather => Box::new(WeatherWidget::default().at(top_left)),
+            WidgetKind::Chart => Box::new(
+                ChartWidget::default()
+                    .with_options(self.options)
+                    .at(top_left),
+            ),
+            WidgetKind::Dashboard => Box::new(DashboardWidget::default().at(top_left)),
+            WidgetKind::PhasePage => Box::new(PhasePageWidget::default().at(top_left)),
+            WidgetKind::BatteryPage => Box::new(BatteryPageWidget::default().at(top_left)),
+            WidgetKind::SystemPage => Box::new(SystemPageWidget::default().at(top_left)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageLayout {
+    // of the panel the layout is for
+    pub size: Size,
+    pub widgets: Vec<WidgetSpec>,
+}
+
+// the keys of one [[widget]] table, checked once the table ends
+#[derive(Default)]
+struct Table {
+    kind: Option<WidgetKind>,
+    region: Option<Rectangle>,
+    gridlines: Option<bool>,
+    fill: Option<bool>,
+}
+
+impl Table {
+    fn finish(self) -> Result<WidgetSpec, LayoutError> {
+        let kind = self.kind.ok_or(LayoutError::MissingType)?;
+        let region = self.region.ok_or(LayoutError::MissingRegion)?;
+        let has_options = self.gridlines.is_some() || self.fill.is_some();
+        if has_options && kind != WidgetKind::Chart {
+            return Err(LayoutError::UnknownKey);
+        }
+        let defaults = ChartOptions::default();
+        Ok(WidgetSpec {
+            kind,
+            region,
+            options: ChartOptions {
+                gridlines: self.gridlines.unwrap_or(defaults.gridlines),
+                fill: self.fill.unwrap_or(defaults.fill),
+            },
+        })
+    }
+}
+
+impl PageLayout {
+    // for the 2.9" panels
+    pub fn parse(config: &str) -> Result<Self, LayoutError> {
+        Self::parse_for(config, Size::new(CANVAS_WIDTH, CANVAS_HEIGHT))
+    }
+    pub fn parse_for(config: &str, size: Size) -> Result<Self, LayoutError> {
+        let mut widgets = Vec::new();
+        let mut table: Option<Table> = None;
+        for line in config.lines() {
+            // the values never contain a #
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line == "[[widget]]" {
+                if let Some(table) = table.replace(Table::default()) {
+                    widgets.push(table.finish()?);
+                }
+                continue;
+            }
+            let (key, value) = line.split_once('=').ok_or(LayoutError::InvalidLine)?;
+            // there are no keys outside of the widgets
+            let table = table.as_mut().ok_or(LayoutError::UnknownKey)?;
+            let value = value.trim();
+            match key.trim() {
+                "type" => {
+                    let name = parse_string(value)?;
+                    table.kind = Some(WidgetKind::parse(name).ok_or(LayoutError::UnknownWidget)?);
+                }
+                "region" => table.region = Some(parse_region(value)?),
+                "gridlines" => table.gridlines = Some(parse_bool(value)?),
+                "fill" => table.fill = Some(parse_bool(value)?),
+                _ => return Err(LayoutError::UnknownKey),
+            }
+        }
+        if let Some(table) = table {
+            widgets.push(table.finish()?);
+        }
+        let layout = PageLayout { size, widgets };
+        layout.validate()?;
+        Ok(layout)
+    }
+    fn validate(&self) -> Result<(), LayoutError> {
+        if self.widgets.is_empty() {
+            return Err(LayoutError::NoWidgets);
+        }
+        let canvas = Rectangle::new(Point::zero(), self.size);
+        for (i, spec) in self.widgets.iter().enumerate() {
+            let size = spec.kind.size();
+            if spec.region.size.width < size.width || spec.region.size.height < size.height {
+                return Err(LayoutError::RegionTooSmall);
+            }
+            if canvas.intersection(&spec.region) != spec.region {
+                return Err(LayoutError::OutOfBounds);
+            }
+            let overlaps = self.widgets[i + 1..]
+                .iter()
+                .any(|other| !spec.region.intersection(&other.region).is_zero_sized());
+            if overlaps {
+                return Err(LayoutError::Overlap);
+            }
+        }
+        Ok(())
+    }
+    // the widgets are drawn in the order of the file
+    pub fn page<T>(&self) -> WidgetPage<T>
+    where
+        T: DrawTarget + OriginDimensions,
+        T::Color: DisplayColor,
+        T::Error: Debug,
+    {
+        let origin = panel::Layout::fit(self.size).offset;
+        self.widgets
+            .iter()
+            .fold(WidgetPage::new("Dashboard"), |page, spec| {
+                page.with_widget(spec.widget(origin))
+            })
+    }
+    // the dashboard of the larger panels
+    pub fn for_panel(spec: &PanelSpec) -> Option<Result<Self, LayoutError>> {
+        spec.dashboard
+            .map(|config| Self::parse_for(config, spec.size()))
+    }
+}
+
+fn parse_string(value: &str) -> Result<&str, LayoutError> {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or(LayoutError::InvalidValue)
+}
+
+fn parse_bool(value: &str) -> Result<bool, LayoutError> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(LayoutError::InvalidValue),
+    }
+}
+
+fn parse_region(value: &str) -> Result<Rectangle, LayoutError> {
+    let values = value
+        .strip_prefix('[')
+        .and_then(|value| value.strip_suffix(']'))
+        .ok_or(LayoutError::InvalidValue)?;
+    let mut values = values.split(',').map(|value| value.trim().parse::<i32>());
+    let mut next = || match values.next() {
+        Some(Ok(value)) => Ok(value),
+        _ => Err(LayoutError::InvalidValue),
+    };
+    let (x, y, width, height) = (next()?, next()?, next()?, next()?);
+    if next().is_ok() || width <= 0 || height <= 0 {
+        return Err(LayoutError::InvalidValue);
+    }
+    Ok(Rectangle::new(
+        Point::new(x, y),
+        Size::new(width as u32, height as u32),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYOUT: &str = r#"
+        # the chart on top, the flow below it
+        [[widget]]
+        type = "chart"
+        region = [0, 0, 147, 56]
+        gridlines = true
+
+        [[widget]]
+        type = "battery"
+        region = [160, 0, 40, 20]
+    "#;
+
+    #[test]
+    fn parses_widgets_in_order() {
+        let layout = PageLayout::parse(LAYOUT).unwrap();
+        assert_eq!(layout.widgets.len(), 2);
+        let chart = layout.widgets[0];
+        assert_eq!(chart.kind, WidgetKind::Chart);
+        assert_eq!(
+            chart.region,
+            Rectangle::new(Point::zero(), Size::new(147, 56))
+        );
+        assert!(chart.options.gridlines);
+        assert!(chart.options.fill);
+        assert_eq!(layout.widgets[1].kind, WidgetKind::Battery);
+    }
+
+    #[test]
+    fn rejects_invalid_layouts() {
+        let parse = |config: &str| PageLayout::parse(config).unwrap_err();
+        assert_eq!(parse(""), LayoutError::NoWidgets);
+        assert_eq!(parse("type = \"chart\""), LayoutError::UnknownKey);
+        assert_eq!(parse("[[widget]]\ntype chart"), LayoutError::InvalidLine);
+        assert_eq!(
+            parse("[[widget]]\ntype = \"clock\""),
+            LayoutError::UnknownWidget
+        );
+        assert_eq!(
+            parse("[[widget]]\nregion = [0, 0, 40, 20]"),
+            LayoutError::MissingType
+        );
+        assert_eq!(
+            parse("[[widget]]\ntype = \"battery\""),
+            LayoutError::MissingRegion
+        );
+        assert_eq!(
+            parse("[[widget]]\ntype = \"battery\"\nregion = [0, 0, 40]"),
+            LayoutError::InvalidValue
+        );
+        // the chart options are only known to the chart
+        assert_eq!(
+            parse("[[widget]]\ntype = \"battery\"\nregion = [0, 0, 40, 20]\nfill = true"),
+            LayoutError::UnknownKey
+        );
+    }
+
+    #[test]
+    fn regions_fit_on_the_canvas_without_overlapping() {
+        let parse = |regions: &[&str]| {
+            let config: String = regions
+                .iter()
+                .map(|region| format!("[[widget]]\ntype = \"battery\"\nregion = {region}\n"))
+                .collect();
+            PageLayout::parse(&config).map(|_| ())
+        };
+        assert_eq!(parse(&["[264, 111, 32, 17]"]), Ok(()));
+        assert_eq!(parse(&["[0, 0, 20, 17]"]), Err(LayoutError::RegionTooSmall));
+        assert_eq!(parse(&["[270, 0, 32, 17]"]), Err(LayoutError::OutOfBounds));
+        assert_eq!(parse(&["[-1, 0, 32, 17]"]), Err(LayoutError::OutOfBounds));
+        assert_eq!(parse(&["[0, 0, 32, 17]", "[32, 0, 32, 17]"]), Ok(()));
+        assert_eq!(
+            parse(&["[0, 0, 32, 17]", "[31, 16, 32, 17]"]),
+            Err(LayoutError::Overlap)
+        );
+    }
+
+    #[test]
+    fn the_larger_panels_have_their_own_dashboard() {
+        for spec in panel::PANELS {
+            match PageLayout::for_panel(&spec) {
+                Some(layout) => {
+                    let layout = layout.unwrap();
+                    assert_eq!(layout.size, spec.size());
+                    assert_eq!(layout.widgets[0].kind, WidgetKind::Dashboard);
+                }
+                None => assert_eq!(spec.width, CANVAS_WIDTH, "{}", spec.name),
+            }
+        }
+        // the dashboard goes where the pages are drawn, so the banners stay on top of it
+        let layout = PageLayout::for_panel(&panel::PANELS[4]).unwrap().unwrap();
+        let page = layout.page::<Blank>();
+        assert_eq!(page.widgets()[0].bounds().top_left, Point::zero());
+        assert_eq!(page.widgets()[3].bounds().top_left, Point::new(0, 328));
+    }
+
+    #[test]
+    fn regions_are_checked_against_the_panel() {
+        let config = "[[widget]]\ntype = \"phase_page\"\nregion = [300, 200, 296, 128]";
+        assert_eq!(
+            PageLayout::parse(config).unwrap_err(),
+            LayoutError::OutOfBounds
+        );
+        assert!(PageLayout::parse_for(config, Size::new(800, 480)).is_ok());
+    }
+
+    // only there to pick the type of the page
+    struct Blank;
+
+    impl OriginDimensions for Blank {
+        fn size(&self) -> Size {
+            Size::new(800, 480)
+        }
+    }
+
+    impl DrawTarget for Blank {
+        type Color = epd_waveshare::color::Color;
+        type Error = core::convert::Infallible;
+        fn draw_iter<I>(&mut self, _pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = embedded_graphics::Pixel<Self::Color>>,
+        {
+            Ok(())
+        }
+    }
+}
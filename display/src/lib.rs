@@ -1,20 +1,64 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+// the longest text on the pages, a line of the 6px font is 49 characters
+pub const TEXT_CAPACITY: usize = 64;
+pub type TextBuffer = heapless::String<TEXT_CAPACITY>;
+
+// like format!, but into a heapless::String on the stack, so drawing does not need the heap
+macro_rules! text {
+    ($($arg:tt)*) => {{
+        let mut text = crate::TextBuffer::new();
+        core::fmt::Write::write_fmt(&mut text, format_args!($($arg)*))
+            .map(|_| text)
+            .map_err(|_| crate::error::DisplayError::TextOverflow)
+    }};
+}
+
 
 
+pub mod alerts;
+pub mod axis;
+pub mod battery;
+pub mod bitmap;
+pub mod buttons;
+pub mod clock;
+pub mod color;
+pub mod dashboard;
+pub mod error;
+pub mod history;
+pub mod layout;
+pub mod metrics;
+pub mod night;
+pub mod notice;
+pub mod pages;
+pub mod panel;
+pub mod phases;
+pub mod prediction;
+pub mod price;
+pub mod state;
+pub mod system;
+pub mod tariff;
+pub mod widgets;
 pub mod prototypes {
     pub mod types {
         include!(concat!(env!("OUT_DIR"), "/prototypes.types.rs"));
     }
 }
-use std::convert::Infallible;
+use alloc::boxed::Box;
+use core::fmt::Debug;
 
-use anyhow::anyhow;
+use crate::error::{DisplayError, Result};
+use core::borrow::BorrowMut;
+use core::marker::PhantomData;
 use embedded_graphics::draw_target::DrawTarget;
 use embedded_graphics::iterator::PixelIteratorExt;
 use embedded_graphics::mono_font::MonoTextStyle;
 use embedded_graphics::mono_font::MonoTextStyleBuilder;
 use embedded_graphics::prelude::Dimensions;
 use embedded_graphics::prelude::OriginDimensions;
-use embedded_graphics::prelude::PixelColor;
+use crate::color::DisplayColor;
 use embedded_graphics::prelude::Point;
 use embedded_graphics::prelude::Size;
 use embedded_graphics::primitives::*;
@@ -22,8 +66,6 @@ use embedded_graphics::text::Text;
 use embedded_graphics::Drawable;
 use embedded_graphics::Pixel;
 //use embedded_graphics_simulator::SimulatorDisplay;
-use epd_waveshare::prelude::DisplayRotation;
-use epd_waveshare::*;
 
 //
 // // this is some code for the simulator display that is located in bin/simulator.rs
@@ -56,22 +98,86 @@ pub enum ConnectionDirection {
     Bottom(bool),
 }
 
-pub struct DisplayBoxed<T: Dimensions + DrawTarget>(pub Box<T>);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChartOptions {
+    // dashed lines at the y ticks and every 6 hours
+    pub gridlines: bool,
+    // dithered area below the prediction
+    pub fill: bool,
+}
+impl Default for ChartOptions {
+    fn default() -> Self {
+        ChartOptions {
+            gridlines: false,
+            fill: true,
+        }
+    }
+}
 
+// what the totals box at the bottom shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TotalsMode {
+    #[default]
+    Energy,
+    Cost,
+}
 
-// pass through the functions for the display. in the recent version of epd-waveshare the
-// display trait seems to have been removed
-// it no longer seems possible to edit/ modify the displays internal buffer
-impl DisplayBoxed<epd2in9_v2::Display2in9> {
-    pub fn buffer(&self) -> &[u8] {
-        self.0.buffer()
+impl TotalsMode {
+    pub fn parse(mode: &str) -> Self {
+        match mode.trim().to_ascii_lowercase().as_str() {
+            "cost" | "eur" => TotalsMode::Cost,
+            _ => TotalsMode::Energy,
+        }
     }
-    pub fn set_rotation(&mut self, rotation: DisplayRotation) {
-        self.0.set_rotation(rotation);
+}
+
+// owns the target, the moved widgets draw through a borrowed view of it instead, see
+// widgets::Shifted, so they do not need a box of their own
+pub struct DisplayBoxed<T: Dimensions + DrawTarget, B: BorrowMut<T> = Box<T>>(
+    pub B,
+    PhantomData<T>,
+);
+
+impl<T: Dimensions + DrawTarget> DisplayBoxed<T> {
+    pub fn new(target: T) -> Self {
+        DisplayBoxed(Box::new(target), PhantomData)
     }
+}
+
+impl<'a, T: Dimensions + DrawTarget> DisplayBoxed<T, &'a mut T> {
+    pub fn borrowed(target: &'a mut T) -> Self {
+        DisplayBoxed(target, PhantomData)
+    }
+}
+
+// the epd displays never fail, an oled or tft behind a bus can. the error of the display is kept
+// for whoever draws on the DisplayBoxed directly, its methods keep its Debug text in
+// DisplayError::Draw
+#[derive(Debug)]
+pub struct DrawError<E>(pub E);
+
+impl<E: Debug> core::fmt::Display for DrawError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "error drawing on the display: {:?}", self.0)
+    }
+}
+
+impl<E: Debug> core::error::Error for DrawError<E> {}
+
+
+// the buffer and the rotation of the display are reached through panel::Panel, in the recent
+// version of epd-waveshare the display trait seems to have been removed
+// it no longer seems possible to edit/ modify the displays internal buffer
+impl<T, B> DisplayBoxed<T, B>
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+    B: BorrowMut<T>,
+{
     // since we no longer have access to the display buffer we need to to modify it indirectly
     // through the drawing apis
-    pub fn set_buf(&mut self, buf: &[u8]) -> Result<(),Infallible>{
+    pub fn set_buf(&mut self, buf: &[u8]) -> Result<(),DrawError<T::Error>>{
         let size = self.size();
         buf.iter().enumerate().map(|(i,byte)| {
             match byte{
@@ -84,7 +190,7 @@ impl DisplayBoxed<epd2in9_v2::Display2in9> {
                             x as i32,
                             y as i32,
                         ),
-                        epd_waveshare::color::Color::Black,
+                        T::Color::BLACK,
                     )
 
                 },
@@ -98,7 +204,7 @@ impl DisplayBoxed<epd2in9_v2::Display2in9> {
                         Point::new(
                             x as i32,y as i32
                         ),
-                        epd_waveshare::color::Color::White,
+                        T::Color::WHITE,
                     )
 
                 }
@@ -118,29 +224,31 @@ impl DisplayBoxed<epd2in9_v2::Display2in9> {
 }
 
 
-impl<T> DrawTarget for DisplayBoxed<T>
+impl<T, B> DrawTarget for DisplayBoxed<T, B>
 where
+    B: BorrowMut<T>,
     T: Dimensions,
-    T: embedded_graphics::draw_target::DrawTarget<Error = Infallible,Color =epd_waveshare::color::Color>,
+    T: embedded_graphics::draw_target::DrawTarget,
     T: embedded_graphics::geometry::OriginDimensions,
+    T::Error: Debug,
 {
-    type Color = epd_waveshare::color::Color;
-    type Error = core::convert::Infallible;
+    type Color = T::Color;
+    type Error = DrawError<T::Error>;
     fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
-        self.0.clear(color)
+        self.0.borrow_mut().clear(color).map_err(DrawError)
     }
     fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
     where
         I: IntoIterator<Item = embedded_graphics::Pixel<Self::Color>>,
     {
-        self.0.draw_iter(pixels)
+        self.0.borrow_mut().draw_iter(pixels).map_err(DrawError)
     }
     fn fill_solid(
         &mut self,
         area: &embedded_graphics::primitives::Rectangle,
         color: Self::Color,
     ) -> Result<(), Self::Error> {
-        self.0.fill_solid(area, color)
+        self.0.borrow_mut().fill_solid(area, color).map_err(DrawError)
     }
     fn fill_contiguous<I>(
         &mut self,
@@ -150,18 +258,19 @@ where
     where
         I: IntoIterator<Item = Self::Color>,
     {
-        self.0.fill_contiguous(area, colors)
+        self.0.borrow_mut().fill_contiguous(area, colors).map_err(DrawError)
     }
 }
 
 // this is some stuff that is neccesary to implement DrawTarget
-impl<T> OriginDimensions for DisplayBoxed<T>
+impl<T, B> OriginDimensions for DisplayBoxed<T, B>
 where
+    B: BorrowMut<T>,
     T: embedded_graphics::geometry::OriginDimensions,
-    T: DrawTarget<Error = Infallible,Color = epd_waveshare::color::Color>,
+    T: DrawTarget,
 {
     fn size(&self) -> embedded_graphics::prelude::Size {
-        self.0.size()
+        self.0.borrow().size()
     }
 }
 
@@ -302,100 +411,215 @@ fn max_in_slice(slice: &[f32]) -> Option<f32> {
     })
 }
 
-impl<T> DisplayBoxed<T>
+impl<T, B> DisplayBoxed<T, B>
 where
+    B: BorrowMut<T>,
     T: Dimensions,
-    T: embedded_graphics::draw_target::DrawTarget<Error = Infallible,Color = epd_waveshare::color::Color>,
+    T: embedded_graphics::draw_target::DrawTarget,
     T: embedded_graphics::geometry::OriginDimensions,
-    T::Color:PixelColor,
+    T::Color:DisplayColor,
+    T::Error: Debug,
 {
-    pub fn draw_chart(&mut self, data: &[f32]) -> anyhow::Result<()> {
+    // data holds one value in kW per chart column, see prediction::resample
+    pub fn draw_chart(
+        &mut self,
+        data: &[f32],
+        actual: &history::ActualSeries,
+        options: ChartOptions,
+    ) -> Result<()> {
         let desc_text_style = MonoTextStyleBuilder::new()
             .font(&embedded_graphics::mono_font::ascii::FONT_4X6)
-            .text_color(epd_waveshare::color::Color::Black)
+            .text_color(T::Color::BLACK)
             .build();
 
+        let start_x = 151;
+        let start_y = 120;
+        let height: u32 = 45;
 
-        let line_style = PrimitiveStyle::with_stroke(epd_waveshare::color::Color::Black, 1);
+        let line_style = PrimitiveStyle::with_stroke(T::Color::BLACK, 1);
         Line::new(Point::new(153, 124), Point::new(286, 124))
             .into_styled(line_style)
             .draw(self)?;
 
+        // x axis, a tick every 3 hours and a label every 6 hours
+        for hour in (3..24).step_by(3) {
+            let x = start_x + (hour * 60 / prediction::COLUMN_MINUTES) as i32;
+            Line::new(Point::new(x, 122), Point::new(x, 123))
+                .into_styled(line_style)
+                .draw(self)?;
+            if options.gridlines && hour % 6 == 0 {
+                let top = Point::new(x, start_y - height as i32);
+                self.draw_dashed_line(top, Point::new(x, start_y))?;
+            }
+        }
+        self.fill_solid(
+            &Rectangle::new(Point::new(184, 124), Size::new(7, 8)),
+            T::Color::WHITE,
+        )?;
         self.fill_solid(
             &Rectangle::new(Point::new(217, 124), Size::new(9, 8)),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
+        )?;
+        self.fill_solid(
+            &Rectangle::new(Point::new(254, 124), Size::new(9, 8)),
+            T::Color::WHITE,
         )?;
         self.fill_solid(
             &Rectangle::new(Point::new(149, 121), Size::new(2, 7)),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
         Text::new("0", Point::new(149, 126), desc_text_style).draw(self)?;
+        Text::new("6", Point::new(186, 126), desc_text_style).draw(self)?;
         Text::new("12", Point::new(218, 126), desc_text_style).draw(self)?;
+        Text::new("18", Point::new(255, 126), desc_text_style).draw(self)?;
         Text::new("24", Point::new(288, 126), desc_text_style).draw(self)?;
 
-        let max = max_in_slice(&data).ok_or(anyhow!("error finding max value"))?;
-        self.fill_solid(
-            &Rectangle::new(Point::new(145, 75), Size::new(10, 8)),
-            epd_waveshare::color::Color::White,
-        )?;
-        if max >= 10.0 {
-            Text::new(
-                &format!("{:.2} kW", max),
-                Point::new(145, 80),
-                desc_text_style,
-            )
-            .draw(self)?;
-        } else {
-            Text::new(
-                &format!("{:.2} kW", max),
-                Point::new(143, 80),
-                desc_text_style,
-            )
-            .draw(self)?;
+        // both curves share the same scale, so the actual values can exceed the prediction
+        let max = max_in_slice(&data).ok_or(DisplayError::MissingData)?;
+        let max = match actual.max() {
+            Some(actual_max) => max.max(actual_max),
+            None => max,
+        };
+        let scale = axis::AxisScale::new(max, 3);
+
+        // y axis, the labels are drawn inside of the chart on the left, since there is no
+        // generation during the night
+        for tick in scale.ticks().skip(1) {
+            let y = start_y - scale.to_pixels(tick, height);
+            Line::new(Point::new(start_x, y), Point::new(start_x + 2, y))
+                .into_styled(line_style)
+                .draw(self)?;
+            if options.gridlines {
+                self.draw_dashed_line(Point::new(start_x + 4, y), Point::new(295, y))?;
+            }
+            let label = if tick == scale.max {
+                text!("{:.*} kW", scale.decimals(), tick)?
+            } else {
+                text!("{:.*}", scale.decimals(), tick)?
+            };
+            Text::new(&label, Point::new(start_x + 4, y + 2), desc_text_style).draw(self)?;
         }
-        let mut averaged = data
-            .chunks(2)
-            .map(|values| {
-                let sum: f32 = values.iter().sum();
-                sum / values.len() as f32
-            })
-            .collect::<Vec<f32>>();
 
-        // scaling to size of display
-        averaged
-            .iter_mut()
-            .for_each(|value| *value = (*value / max * 45.0).round());
-
-        let start_x = 151;
-        let start_y = 120;
-        let points = averaged
+        let points = data
             .iter()
+            .take(prediction::CHART_COLUMNS)
             .enumerate()
             .map(|(i, &v)| {
                 let x = start_x + i as i32;
-                let y = start_y - v as i32;
+                let y = start_y - scale.to_pixels(v, height);
                 Point::new(x, y)
             })
-            .collect::<Vec<Point>>();
+            .collect::<heapless::Vec<Point, { prediction::CHART_COLUMNS }>>();
+
+        // sparse dither below the prediction, denser patterns smear on the e-paper
+        if options.fill {
+            points
+                .iter()
+                .flat_map(|point| {
+                    (point.y + 1..=start_y)
+                        .filter(move |y| y % 2 == 0 && (point.x + y / 2) % 2 == 0)
+                        .map(move |y| Point::new(point.x, y))
+                })
+                .map(|point| Pixel(point, T::Color::BLACK))
+                .draw(self)?;
+        }
 
-        Polyline::new(&points).into_styled(line_style).draw(self)?;
+        // the prediction is dotted, so the actual generation stays readable on top of it
+        points
+            .windows(2)
+            .flat_map(|segment| Line::new(segment[0], segment[1]).points().skip(1))
+            .enumerate()
+            .filter(|(i, _)| i % 2 == 0)
+            .map(|(_, point)| Pixel(point, T::Color::BLACK))
+            .draw(self)?;
 
-        Text::new("Prediction",Point::new(256,76),desc_text_style).draw(self)?;
+        // the actual generation is drawn solid, one point per chart column. gaps in the data
+        // split the line, so we don't connect values across hours without data
+        let buckets_per_column = (prediction::COLUMN_MINUTES / history::BUCKET_MINUTES) as usize;
+        let mut segment: heapless::Vec<Point, { prediction::CHART_COLUMNS }> = heapless::Vec::new();
+        for column in 0..prediction::CHART_COLUMNS {
+            match actual.average(column * buckets_per_column..(column + 1) * buckets_per_column) {
+                // one point per column at most, so it always fits
+                Some(v) => {
+                    let _ = segment.push(Point::new(
+                        start_x + column as i32,
+                        start_y - scale.to_pixels(v, height),
+                    ));
+                }
+                None => {
+                    self.draw_actual_segment(&segment, line_style)?;
+                    segment.clear();
+                }
+            }
+        }
+        self.draw_actual_segment(&segment, line_style)?;
+
+        // now marker
+        let now_column = actual
+            .now_minute()
+            .map(|minute| (minute / prediction::COLUMN_MINUTES) as usize);
+        if let Some(column) = now_column {
+            let x = start_x + column as i32;
+            Line::new(Point::new(x, start_y - height as i32), Point::new(x, start_y))
+                .points()
+                .step_by(3)
+                .map(|point| Pixel(point, T::Color::BLACK))
+                .draw(self)?;
+        }
+
+        // expected energy for the whole day and for what is left of it
+        let total = prediction::energy_kwh(data, 0);
+        Text::new(
+            &text!("Pred {:.1}kWh", total)?,
+            Point::new(246, 80),
+            desc_text_style,
+        )
+        .draw(self)?;
+        if let Some(column) = now_column {
+            let remaining = prediction::energy_kwh(data, column);
+            Text::new(
+                &text!("Left {:.1}kWh", remaining)?,
+                Point::new(246, 87),
+                desc_text_style,
+            )
+            .draw(self)?;
+        }
 
         Ok(())
     }
-    pub fn new_total(&mut self, house: &str, solar: &str) -> anyhow::Result<()> {
+    fn draw_dashed_line(&mut self, start: Point, end: Point) -> Result<()> {
+        Line::new(start, end)
+            .points()
+            .enumerate()
+            .filter(|(i, _)| i % 4 == 0)
+            .map(|(_, point)| Pixel(point, T::Color::BLACK))
+            .draw(self)?;
+        Ok(())
+    }
+    fn draw_actual_segment(
+        &mut self,
+        segment: &[Point],
+        line_style: PrimitiveStyle<T::Color>,
+    ) -> Result<()> {
+        match segment.len() {
+            0 => {}
+            1 => Pixel(segment[0], T::Color::BLACK).draw(self)?,
+            _ => Polyline::new(segment).into_styled(line_style).draw(self)?,
+        }
+        Ok(())
+    }
+    pub fn new_total(&mut self, house: &str, solar: &str) -> Result<()> {
         let desc_text_style = MonoTextStyleBuilder::new()
             .font(&embedded_graphics::mono_font::ascii::FONT_4X6)
-            .text_color(epd_waveshare::color::Color::Black)
+            .text_color(T::Color::BLACK)
             .build();
 
         embedded_graphics::primitives::Rectangle::new(Point::new(100, 91), Size::new(45, 38))
-            .into_styled(PrimitiveStyle::with_stroke(epd_waveshare::color::Color::Black, 1))
+            .into_styled(PrimitiveStyle::with_stroke(T::Color::BLACK, 1))
             .draw(self)?;
         self.fill_solid(
             &Rectangle::new(Point::new(113, 88), Size::new(20, 7)),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
 
         Text::new("Total", Point::new(114, 93), desc_text_style).draw(self)?;
@@ -407,11 +631,11 @@ where
                 let y = 95 + (idx / 18);
                 let color = {
                     if num == &0 {
-                        epd_waveshare::color::Color::White
+                        T::Color::WHITE
                     } else if num == &1 {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     } else {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     }
                 };
                 Pixel(Point::new(x as i32, y as i32), color)
@@ -431,11 +655,11 @@ where
                 let y = 112 + (idx / 18);
                 let color = {
                     if num == &0 {
-                        epd_waveshare::color::Color::White
+                        T::Color::WHITE
                     } else if num == &1 {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     } else {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     }
                 };
                 Pixel(Point::new(x as i32, y as i32), color)
@@ -451,14 +675,14 @@ where
         Ok(())
     }
 
-    pub fn update_total_new(&mut self, house: &str, solar: &str) -> anyhow::Result<()> {
+    pub fn update_total_new(&mut self, house: &str, solar: &str) -> Result<()> {
         self.fill_solid(
             &Rectangle::new(Point::new(120, 97), Size::new(23, 30)),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
         let desc_text_style = MonoTextStyleBuilder::new()
             .font(&embedded_graphics::mono_font::ascii::FONT_4X6)
-            .text_color(epd_waveshare::color::Color::Black)
+            .text_color(T::Color::BLACK)
             .build();
         if solar.len() > 3 {
             Text::new(solar, Point::new(122, 104), desc_text_style).draw(self)?;
@@ -472,16 +696,72 @@ where
         }
         Ok(())
     }
+    // alternative to the kWh totals, draws the same box with the costs of the day in EUR
+    pub fn new_total_cost(&mut self) -> Result<()> {
+        let desc_text_style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_4X6)
+            .text_color(T::Color::BLACK)
+            .build();
+
+        self.fill_solid(
+            &Rectangle::new(Point::new(100, 88), Size::new(46, 40)),
+            T::Color::WHITE,
+        )?;
+        embedded_graphics::primitives::Rectangle::new(Point::new(100, 91), Size::new(45, 38))
+            .into_styled(PrimitiveStyle::with_stroke(T::Color::BLACK, 1))
+            .draw(self)?;
+        self.fill_solid(
+            &Rectangle::new(Point::new(115, 88), Size::new(16, 7)),
+            T::Color::WHITE,
+        )?;
+        Text::new("EUR", Point::new(117, 93), desc_text_style).draw(self)?;
+        self.update_total_cost(tariff::Costs::default())
+    }
+    pub fn update_total_cost(&mut self, costs: tariff::Costs) -> Result<()> {
+        self.fill_solid(
+            &Rectangle::new(Point::new(101, 96), Size::new(43, 31)),
+            T::Color::WHITE,
+        )?;
+        let desc_text_style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_4X6)
+            .text_color(T::Color::BLACK)
+            .build();
+        let rows = [
+            ("Save", costs.saved, 103),
+            ("Feed", costs.earned, 113),
+            ("Grid", costs.spent, 123),
+        ];
+        for (label, value, y) in rows {
+            Text::new(label, Point::new(103, y), desc_text_style).draw(self)?;
+            // right aligned, so the decimal points line up
+            let value = text!("{:.2}", value)?;
+            let x = 143 - value.len() as i32 * 4;
+            Text::new(&value, Point::new(x, y), desc_text_style).draw(self)?;
+        }
+        Ok(())
+    }
     pub fn draw_default_display<'a>(
         &mut self,
-        style: MonoTextStyle<'a, epd_waveshare::color::Color>,
-    ) -> anyhow::Result<()> {
+        style: MonoTextStyle<'a, T::Color>,
+    ) -> Result<()> {
         self.draw_default_battery_percentage()?;
+        self.draw_default_flow(style)?;
+        self.draw_dashboard_frame()?;
+        self.draw_default_sun()?;
+        self.draw_default_weather_grid()?;
+        self.new_total("00.00", "00.00")?;
+        Ok(())
+    }
+    // the circles with their icons, the box of the time and the placeholder values
+    pub fn draw_default_flow<'a>(
+        &mut self,
+        style: MonoTextStyle<'a, T::Color>,
+    ) -> Result<()> {
         //Circle top
         Circle::new(Point::new(55, 2), 40)
             .into_styled(
                 PrimitiveStyleBuilder::new()
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .stroke_width(2)
                     .build(),
             )
@@ -490,7 +770,7 @@ where
         Circle::new(Point::new(55, 86), 40)
             .into_styled(
                 PrimitiveStyleBuilder::new()
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .stroke_width(2)
                     .build(),
             )
@@ -501,7 +781,7 @@ where
             .into_styled(
                 PrimitiveStyleBuilder::new()
                     .stroke_width(2)
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .build(),
             )
             .draw(self)?;
@@ -511,26 +791,26 @@ where
             .into_styled(
                 PrimitiveStyleBuilder::new()
                     .stroke_width(2)
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .build(),
             )
             .draw(self)?;
         // Solids for the icons
         self.fill_solid(
             &Rectangle::new(Point::new(66, 0), Size::new(18, 15)),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
         self.fill_solid(
             &Rectangle::new(Point::new(66, 84), Size::new(18, 15)),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
         self.fill_solid(
             &Rectangle::new(Point::new(24, 42), Size::new(18, 15)),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
         self.fill_solid(
             &Rectangle::new(Point::new(108, 42), Size::new(18, 15)),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
 
         // icons
@@ -542,11 +822,11 @@ where
                 let y = 0 + (idx / 18);
                 let color = {
                     if num == &0 {
-                        epd_waveshare::color::Color::White
+                        T::Color::WHITE
                     } else if num == &1 {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     } else {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     }
                 };
                 Pixel(Point::new(x as i32, y as i32), color)
@@ -560,11 +840,11 @@ where
                 let y = 43 + (idx / 18);
                 let color = {
                     if num == &0 {
-                        epd_waveshare::color::Color::White
+                        T::Color::WHITE
                     } else if num == &1 {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     } else {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     }
                 };
                 Pixel(Point::new(x as i32, y as i32), color)
@@ -578,11 +858,11 @@ where
                 let y = 84 + (idx / 18);
                 let color = {
                     if num == &0 {
-                        epd_waveshare::color::Color::White
+                        T::Color::WHITE
                     } else if num == &1 {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     } else {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     }
                 };
                 Pixel(Point::new(x as i32, y as i32), color)
@@ -591,7 +871,7 @@ where
         Line::new(Point::new(66 + 12, 85), Point::new(71, 96))
             .into_styled(
                 PrimitiveStyleBuilder::new()
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .stroke_width(1)
                     .build(),
             )
@@ -605,28 +885,20 @@ where
                 let y = 42 + (idx / 18);
                 let color = {
                     if num == &0 {
-                        epd_waveshare::color::Color::White
+                        T::Color::WHITE
                     } else if num == &1 {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     } else {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     }
                 };
                 Pixel(Point::new(x as i32, y as i32), color)
             })
             .draw(self)?;
-        Line::new(Point::new(149, 0), Point::new(149, 128))
-            .into_styled(
-                PrimitiveStyleBuilder::new()
-                    .stroke_color(epd_waveshare::color::Color::Black)
-                    .stroke_width(2)
-                    .build(),
-            )
-            .draw(self)?;
         Line::new(Point::new(103, 0), Point::new(103, 20))
             .into_styled(
                 PrimitiveStyleBuilder::new()
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .stroke_width(2)
                     .build(),
             )
@@ -634,7 +906,7 @@ where
         Line::new(Point::new(103, 20), Point::new(149, 20))
             .into_styled(
                 PrimitiveStyleBuilder::new()
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .stroke_width(2)
                     .build(),
             )
@@ -645,19 +917,19 @@ where
             Point::new(71, 120),
             MonoTextStyleBuilder::new()
                 .font(&embedded_graphics::mono_font::ascii::FONT_9X15)
-                .text_color(epd_waveshare::color::Color::Black)
+                .text_color(T::Color::BLACK)
                 .build(),
         )
         .draw(self)?;
 
         let pos = [(65, 23), (22, 65), (107, 65)];
-        pos.iter().try_for_each(|pos| -> anyhow::Result<()> {
+        pos.iter().try_for_each(|pos| -> Result<()> {
             Text::new(
                 "kW",
                 Point::new(pos.0 + 5, pos.1 + 11),
                 MonoTextStyleBuilder::new()
                     .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
-                    .text_color(epd_waveshare::color::Color::Black)
+                    .text_color(T::Color::BLACK)
                     .build(),
             )
             .draw(self)?;
@@ -669,24 +941,22 @@ where
             Point::new(71, 120),
             MonoTextStyleBuilder::new()
                 .font(&embedded_graphics::mono_font::ascii::FONT_9X15)
-                .text_color(epd_waveshare::color::Color::Black)
+                .text_color(T::Color::BLACK)
                 .build(),
         )
         .draw(self)?;
         self.draw_text(style, "0.00", "-0.00", "0.00", "-0.00", "0:00PM")?;
-        self.draw_default_weather()?;
-        self.new_total("00.00", "00.00")?;
         Ok(())
     }
     pub fn draw_text<'a>(
         &mut self,
-        style: MonoTextStyle<'a, epd_waveshare::color::Color>,
+        style: MonoTextStyle<'a, T::Color>,
         circle_top: &'a str,
         circle_bottom: &'a str,
         circle_left: &'a str,
         circle_right: &'a str,
         update: &'a str,
-    ) -> anyhow::Result<()> {
+    ) -> Result<()> {
         // Circle top
         // normally we have 3 digits
         if circle_top.len() == 1 {
@@ -727,60 +997,60 @@ where
 
         Ok(())
     }
-    pub fn clear_text(&mut self) -> anyhow::Result<()> {
+    pub fn clear_text(&mut self) -> Result<()> {
         self.fill_solid(
             &Rectangle::new(
                 Point::new(65, 15),
                 embedded_graphics::prelude::Size::new(25, 10),
             ),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
         self.fill_solid(
             &Rectangle::new(
                 Point::new(60, 99),
                 embedded_graphics::prelude::Size::new(30, 10),
             ),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
         self.fill_solid(
             &Rectangle::new(
                 Point::new(22, 57),
                 embedded_graphics::prelude::Size::new(25, 10),
             ),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
         self.fill_solid(
             &Rectangle::new(
                 Point::new(102, 57),
                 embedded_graphics::prelude::Size::new(30, 10),
             ),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
         self.fill_solid(
             &Rectangle::new(
                 Point::new(105, 1),
                 embedded_graphics::prelude::Size::new(42, 18),
             ),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
         return Ok(());
     }
     pub fn display_error_message<'a>(
         &mut self,
         message: &str,
-        style: MonoTextStyle<'a, epd_waveshare::color::Color>,
-    ) -> anyhow::Result<()> {
+        style: MonoTextStyle<'a, T::Color>,
+    ) -> Result<()> {
         Text::new(message, Point::new(58, 100), style).draw(self)?;
         Ok(())
     }
-    pub fn draw_connections(&mut self, connection: ConnectionDirection) -> anyhow::Result<()> {
+    pub fn draw_connections(&mut self, connection: ConnectionDirection) -> Result<()> {
         match connection {
             ConnectionDirection::Top(arr) => {
                 // Line middle to top circle
                 Line::new(Point::new(75, 64), Point::new(75, 44))
                     .into_styled(
                         PrimitiveStyleBuilder::new()
-                            .stroke_color(epd_waveshare::color::Color::Black)
+                            .stroke_color(T::Color::BLACK)
                             .stroke_width(2)
                             .build(),
                     )
@@ -797,7 +1067,7 @@ where
                 Line::new(Point::new(74, 63), Point::new(55, 63))
                     .into_styled(
                         PrimitiveStyleBuilder::new()
-                            .stroke_color(epd_waveshare::color::Color::Black)
+                            .stroke_color(T::Color::BLACK)
                             .stroke_width(2)
                             .build(),
                     )
@@ -810,11 +1080,12 @@ where
                 }
             }
             ConnectionDirection::Right(arr) => {
-                // Line middle to right circle
+                // Line middle to right circle, importing from the grid is drawn in the accent
+                // colour
                 Line::new(Point::new(74, 64), Point::new(94, 64))
                     .into_styled(
                         PrimitiveStyleBuilder::new()
-                            .stroke_color(epd_waveshare::color::Color::Black)
+                            .stroke_color(if arr { T::Color::BLACK } else { T::Color::ACCENT })
                             .stroke_width(2)
                             .build(),
                     )
@@ -827,11 +1098,11 @@ where
                 }
             }
             ConnectionDirection::Bottom(arr) => {
-                // Line middle to bottom circle
+                // Line middle to bottom circle, discharging is drawn in the accent colour
                 Line::new(Point::new(74, 64), Point::new(74, 82))
                     .into_styled(
                         PrimitiveStyleBuilder::new()
-                            .stroke_color(epd_waveshare::color::Color::Black)
+                            .stroke_color(if arr { T::Color::BLACK } else { T::Color::ACCENT })
                             .stroke_width(2)
                             .build(),
                     )
@@ -848,12 +1119,12 @@ where
 
         Ok(())
     }
-    pub fn set_connected(&mut self) -> anyhow::Result<()> {
+    pub fn set_connected(&mut self) -> Result<()> {
         Line::new(Point::new(0, 118), Point::new(40, 118))
             .into_styled(
                 PrimitiveStyleBuilder::new()
                     .stroke_width(1)
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .build(),
             )
             .draw(self)?;
@@ -861,7 +1132,7 @@ where
             .into_styled(
                 PrimitiveStyleBuilder::new()
                     .stroke_width(1)
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .build(),
             )
             .draw(self)?;
@@ -870,13 +1141,13 @@ where
             Point::new(2, 125),
             MonoTextStyleBuilder::new()
                 .font(&embedded_graphics::mono_font::ascii::FONT_4X6)
-                .text_color(epd_waveshare::color::Color::Black)
+                .text_color(T::Color::BLACK)
                 .build(),
         )
         .draw(self)?;
         Ok(())
     }
-    fn draw_arrow(&mut self, direction: ArrowDirection) -> anyhow::Result<()> {
+    fn draw_arrow(&mut self, direction: ArrowDirection) -> Result<()> {
         match direction {
             ArrowDirection::Up => {
                 // Arrow up
@@ -884,7 +1155,7 @@ where
                     .into_styled(
                         PrimitiveStyleBuilder::new()
                             .stroke_width(1)
-                            .stroke_color(epd_waveshare::color::Color::Black)
+                            .stroke_color(T::Color::BLACK)
                             .build(),
                     )
                     .draw(self)?;
@@ -892,7 +1163,7 @@ where
                     .into_styled(
                         PrimitiveStyleBuilder::new()
                             .stroke_width(1)
-                            .stroke_color(epd_waveshare::color::Color::Black)
+                            .stroke_color(T::Color::BLACK)
                             .build(),
                     )
                     .draw(self)?;
@@ -904,7 +1175,7 @@ where
                     .into_styled(
                         PrimitiveStyleBuilder::new()
                             .stroke_width(1)
-                            .stroke_color(epd_waveshare::color::Color::Black)
+                            .stroke_color(T::Color::BLACK)
                             .build(),
                     )
                     .draw(self)?;
@@ -912,7 +1183,7 @@ where
                     .into_styled(
                         PrimitiveStyleBuilder::new()
                             .stroke_width(1)
-                            .stroke_color(epd_waveshare::color::Color::Black)
+                            .stroke_color(T::Color::BLACK)
                             .build(),
                     )
                     .draw(self)?;
@@ -924,7 +1195,7 @@ where
                     .into_styled(
                         PrimitiveStyleBuilder::new()
                             .stroke_width(1)
-                            .stroke_color(epd_waveshare::color::Color::Black)
+                            .stroke_color(T::Color::BLACK)
                             .build(),
                     )
                     .draw(self)?;
@@ -932,7 +1203,7 @@ where
                     .into_styled(
                         PrimitiveStyleBuilder::new()
                             .stroke_width(1)
-                            .stroke_color(epd_waveshare::color::Color::Black)
+                            .stroke_color(T::Color::BLACK)
                             .build(),
                     )
                     .draw(self)?;
@@ -944,7 +1215,7 @@ where
                     .into_styled(
                         PrimitiveStyleBuilder::new()
                             .stroke_width(1)
-                            .stroke_color(epd_waveshare::color::Color::Black)
+                            .stroke_color(T::Color::BLACK)
                             .build(),
                     )
                     .draw(self)?;
@@ -952,7 +1223,7 @@ where
                     .into_styled(
                         PrimitiveStyleBuilder::new()
                             .stroke_width(1)
-                            .stroke_color(epd_waveshare::color::Color::Black)
+                            .stroke_color(T::Color::BLACK)
                             .build(),
                     )
                     .draw(self)?;
@@ -961,13 +1232,13 @@ where
         }
         Ok(())
     }
-    pub fn update_battery_percentage(&mut self, percentage: &str) -> anyhow::Result<()> {
+    pub fn update_battery_percentage(&mut self, percentage: &str) -> Result<()> {
         self.fill_solid(
             &Rectangle::new(Point::new(1, 1), Size::new(28, 12)),
-            epd_waveshare::color::Color::White.into(),
+            T::Color::WHITE.into(),
         )?;
         if percentage.len() > 3 || percentage.len() < 1 {
-            return Err(anyhow!("errro input sequence too long"));
+            return Err(DisplayError::InvalidInput);
         }
         let offset = {
             if percentage.len() == 3 {
@@ -979,22 +1250,22 @@ where
             }
         };
         Text::new(
-            &format!("{percentage}%"),
+            &text!("{percentage}%")?,
             Point::new(3 + offset, 10),
             MonoTextStyleBuilder::new()
                 .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
-                .text_color(epd_waveshare::color::Color::Black)
+                .text_color(T::Color::BLACK)
                 .build(),
         )
         .draw(self)?;
 
         Ok(())
     }
-    fn draw_default_battery_percentage(&mut self) -> anyhow::Result<()> {
+    pub fn draw_default_battery_percentage(&mut self) -> Result<()> {
         Line::new(Point::new(30, 0), Point::new(30, 15))
             .into_styled(
                 PrimitiveStyleBuilder::new()
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .stroke_width(2)
                     .build(),
             )
@@ -1003,7 +1274,7 @@ where
         Line::new(Point::new(0, 15), Point::new(30, 15))
             .into_styled(
                 PrimitiveStyleBuilder::new()
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .stroke_width(2)
                     .build(),
             )
@@ -1013,29 +1284,645 @@ where
             Point::new(3, 10),
             MonoTextStyleBuilder::new()
                 .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
-                .text_color(epd_waveshare::color::Color::Black)
+                .text_color(T::Color::BLACK)
                 .build(),
         )
         .draw(self)?;
         Ok(())
     }
-    pub fn update_sun_data(&mut self, sunrise: &str, sunset: &str) -> anyhow::Result<()> {
+    // status strip below the battery percentage, showing the state of the senec system
+    pub fn update_system_state(
+        &mut self,
+        system_state: state::SystemState,
+        charging: bool,
+        boosting: bool,
+    ) -> Result<()> {
+        let area = Rectangle::new(Point::new(0, 18), Size::new(53, 24));
+        let (background, foreground) = if system_state.is_emphasised() {
+            (
+                T::Color::BLACK,
+                T::Color::WHITE,
+            )
+        } else {
+            (
+                T::Color::WHITE,
+                T::Color::BLACK,
+            )
+        };
+        self.fill_solid(&area, background)?;
         let style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_4X6)
+            .text_color(foreground)
+            .build();
+
+        // 13 characters fit into one line, longer labels are wrapped at the last space
+        let label = system_state.label();
+        let (first, second) = if label.len() > 13 {
+            match label[..13].rfind(' ') {
+                Some(idx) => (&label[..idx], &label[idx + 1..]),
+                None => (label, ""),
+            }
+        } else {
+            (label, "")
+        };
+        Text::new(first, Point::new(2, 24), style).draw(self)?;
+        Text::new(second, Point::new(2, 31), style).draw(self)?;
+
+        let flags = match (charging, boosting) {
+            (true, true) => "CHG BOOST",
+            (true, false) => "CHG",
+            (false, true) => "BOOST",
+            (false, false) => "",
+        };
+        Text::new(flags, Point::new(2, 38), style).draw(self)?;
+        Ok(())
+    }
+    // fifth node of the power flow, top right between the time and the grid. it is connected
+    // diagonally to the center and hidden if there is no wallbox. has to be redrawn after the
+    // connections were cleared
+    pub fn update_wallbox(
+        &mut self,
+        wallbox: Option<&prototypes::types::WallboxNew>,
+    ) -> Result<()> {
+        self.fill_solid(
+            &Rectangle::new(Point::new(96, 22), Size::new(52, 22)),
+            T::Color::WHITE,
+        )?;
+        // the arrow reaches out of the area of the other connections
+        self.fill_solid(
+            &Rectangle::new(Point::new(92, 38), Size::new(9, 9)),
+            T::Color::WHITE,
+        )?;
+        let wallbox = match wallbox {
+            Some(wallbox) if wallbox.present => wallbox,
+            _ => return Ok(()),
+        };
+        let line_style = PrimitiveStyleBuilder::new()
+            .stroke_color(T::Color::BLACK)
+            .stroke_width(1)
+            .build();
+        let desc_text_style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_4X6)
+            .text_color(T::Color::BLACK)
+            .build();
+
+        // the border is dashed while no car is connected
+        let node = Rectangle::new(Point::new(100, 22), Size::new(46, 21));
+        if wallbox.car_connected {
+            RoundedRectangle::with_equal_corners(node, Size::new(3, 3))
+                .into_styled(line_style)
+                .draw(self)?;
+        } else {
+            self.draw_dashed_line(Point::new(100, 22), Point::new(145, 22))?;
+            self.draw_dashed_line(Point::new(100, 42), Point::new(145, 42))?;
+            self.draw_dashed_line(Point::new(100, 22), Point::new(100, 42))?;
+            self.draw_dashed_line(Point::new(145, 22), Point::new(145, 42))?;
+        }
+
+        let power = wallbox.charging_power.trim();
+        let charging = power.parse::<f32>().map(|kw| kw > 0.0).unwrap_or(false);
+        let power_text = text!("{}kW", if power.is_empty() { "--" } else { power })?;
+        Text::new("EV", Point::new(103, 30), desc_text_style).draw(self)?;
+        Text::new(
+            &power_text,
+            Point::new(143 - power_text.len() as i32 * 4, 30),
+            desc_text_style,
+        )
+        .draw(self)?;
+        // the session energy while a car is connected, otherwise what the wallbox is doing
+        let energy = text!("{}kWh", wallbox.session_energy.trim())?;
+        let detail = if wallbox.car_connected && !wallbox.session_energy.is_empty() {
+            energy.as_str()
+        } else if wallbox.car_connected {
+            wallbox.state.as_str()
+        } else {
+            "no car"
+        };
+        // the state is free text of the wallbox and not always ascii
+        let detail = match detail.char_indices().nth(10) {
+            Some((idx, _)) => &detail[..idx],
+            None => detail,
+        };
+        Text::new(detail, Point::new(103, 38), desc_text_style).draw(self)?;
+
+        // connection to the center, with an arrow towards the car while charging
+        Line::new(Point::new(77, 62), Point::new(99, 40))
+            .into_styled(
+                PrimitiveStyleBuilder::new()
+                    .stroke_color(T::Color::BLACK)
+                    .stroke_width(2)
+                    .build(),
+            )
+            .draw(self)?;
+        if charging {
+            Line::new(Point::new(99, 40), Point::new(93, 40))
+                .into_styled(line_style)
+                .draw(self)?;
+            Line::new(Point::new(99, 40), Point::new(99, 46))
+                .into_styled(line_style)
+                .draw(self)?;
+        }
+        Ok(())
+    }
+    // detail page with the three phases side by side. the grid power is drawn as a bar around
+    // a zero line, importing up and exporting down, the most loaded phase has an inverted label
+    pub fn draw_phase_page(&mut self, summary: &phases::PhaseSummary) -> Result<()> {
+        self.clear(T::Color::WHITE)?;
+        let title_style = MonoTextStyleBuilder::new()
             .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
-            .text_color(epd_waveshare::color::Color::Black)
+            .text_color(T::Color::BLACK)
+            .build();
+        let value_style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_5X8)
+            .text_color(T::Color::BLACK)
+            .build();
+        let line_style = PrimitiveStyle::with_stroke(T::Color::BLACK, 1);
+
+        Text::new("Phases", Point::new(2, 11), title_style).draw(self)?;
+        let frequency = text!("{:.2} Hz", summary.frequency)?;
+        Text::new(
+            &frequency,
+            Point::new(294 - frequency.len() as i32 * 6, 11),
+            title_style,
+        )
+        .draw(self)?;
+        Line::new(Point::new(0, 14), Point::new(295, 14))
+            .into_styled(line_style)
+            .draw(self)?;
+        Line::new(Point::new(0, 110), Point::new(295, 110))
+            .into_styled(line_style)
+            .draw(self)?;
+
+        // all bars share the same scale, at least 1 kW so small loads stay small
+        let max = summary
+            .phases
+            .iter()
+            .fold(1.0f32, |acc, phase| acc.max(phase.grid_power.abs()));
+        let most_loaded = summary.most_loaded();
+        for (idx, phase) in summary.phases.iter().enumerate() {
+            let x = idx as i32 * 99;
+            if idx > 0 {
+                Line::new(Point::new(x - 1, 15), Point::new(x - 1, 109))
+                    .into_styled(line_style)
+                    .draw(self)?;
+            }
+
+            let label = text!("L{}", idx + 1)?;
+            let label_style = if idx == most_loaded && phase.grid_power != 0.0 {
+                self.fill_solid(
+                    &Rectangle::new(Point::new(x + 2, 18), Size::new(21, 17)),
+                    T::Color::BLACK,
+                )?;
+                MonoTextStyleBuilder::new()
+                    .font(&embedded_graphics::mono_font::ascii::FONT_9X15)
+                    .text_color(T::Color::WHITE)
+                    .build()
+            } else {
+                MonoTextStyleBuilder::new()
+                    .font(&embedded_graphics::mono_font::ascii::FONT_9X15)
+                    .text_color(T::Color::BLACK)
+                    .build()
+            };
+            Text::new(&label, Point::new(x + 4, 31), label_style).draw(self)?;
+
+            Text::new("Grid", Point::new(x + 4, 48), value_style).draw(self)?;
+            Text::new(
+                &text!("{:.2}kW", phase.grid_power)?,
+                Point::new(x + 4, 57),
+                value_style,
+            )
+            .draw(self)?;
+            Text::new("Inverter", Point::new(x + 4, 72), value_style).draw(self)?;
+            Text::new(
+                &text!("{:.2}kW", phase.inverter_power)?,
+                Point::new(x + 4, 81),
+                value_style,
+            )
+            .draw(self)?;
+            Text::new(
+                &text!("{:.1}V", phase.voltage)?,
+                Point::new(x + 4, 100),
+                value_style,
+            )
+            .draw(self)?;
+
+            // bar of the grid power, 40 pixels in each direction
+            let bar_x = x + 72;
+            let zero_y = 62;
+            Line::new(Point::new(bar_x - 2, zero_y), Point::new(bar_x + 19, zero_y))
+                .into_styled(line_style)
+                .draw(self)?;
+            let length = (phase.grid_power.abs() / max * 40.0) as u32;
+            if length > 0 {
+                let top = if phase.grid_power > 0.0 {
+                    zero_y - length as i32
+                } else {
+                    zero_y + 1
+                };
+                self.fill_solid(
+                    &Rectangle::new(Point::new(bar_x, top), Size::new(18, length)),
+                    T::Color::BLACK,
+                )?;
+            }
+        }
+
+        let imbalance = match summary.imbalance_percent() {
+            Some(percent) => text!(
+                "Imbalance {:.2}kW ({:.0}%)",
+                summary.imbalance_kw(),
+                percent
+            )?,
+            None => text!("Imbalance {:.2}kW", summary.imbalance_kw())?,
+        };
+        Text::new(&imbalance, Point::new(2, 123), title_style).draw(self)?;
+        Ok(())
+    }
+    // detail page of the battery with a large state of charge gauge on the left and the
+    // details of the pack on the right
+    pub fn draw_battery_page(&mut self, status: &battery::BatteryStatus) -> Result<()> {
+        self.clear(T::Color::WHITE)?;
+        let title_style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
+            .text_color(T::Color::BLACK)
+            .build();
+        let line_style = PrimitiveStyle::with_stroke(T::Color::BLACK, 1);
+
+        Text::new("Battery", Point::new(2, 11), title_style).draw(self)?;
+        if let Some(power) = status.power_kw {
+            let power = text!("{:+.2} kW", power)?;
+            Text::new(
+                &power,
+                Point::new(294 - power.len() as i32 * 6, 11),
+                title_style,
+            )
+            .draw(self)?;
+        }
+        Line::new(Point::new(0, 14), Point::new(295, 14))
+            .into_styled(line_style)
+            .draw(self)?;
+        Line::new(Point::new(0, 110), Point::new(295, 110))
+            .into_styled(line_style)
+            .draw(self)?;
+
+        // gauge in the shape of a battery
+        Rectangle::new(Point::new(8, 26), Size::new(100, 56))
+            .into_styled(PrimitiveStyle::with_stroke(
+                T::Color::BLACK,
+                2,
+            ))
+            .draw(self)?;
+        self.fill_solid(
+            &Rectangle::new(Point::new(108, 44), Size::new(6, 20)),
+            T::Color::BLACK,
+        )?;
+        let soc_text = match status.soc_percent {
+            Some(soc) => {
+                let width = (soc.clamp(0.0, 100.0) / 100.0 * 92.0) as u32;
+                self.fill_solid(
+                    &Rectangle::new(Point::new(12, 30), Size::new(width, 48)),
+                    T::Color::BLACK,
+                )?;
+                text!("{:.0}%", soc)?
+            }
+            None => text!("--%")?,
+        };
+        let soc_style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_9X15)
+            .text_color(T::Color::BLACK)
+            .build();
+        Text::new(
+            &soc_text,
+            Point::new(58 - soc_text.len() as i32 * 9 / 2, 100),
+            soc_style,
+        )
+        .draw(self)?;
+
+        Line::new(Point::new(124, 15), Point::new(124, 109))
+            .into_styled(line_style)
+            .draw(self)?;
+        let rows = match &status.details {
+            Some(details) => [
+                ("Voltage", text!("{:.1} V", details.voltage)?),
+                ("Current", text!("{:+.1} A", details.current)?),
+                ("Temperature", text!("{:.1} C", details.temperature)?),
+                ("Cycles", text!("{}", details.cycles)?),
+                ("Health", text!("{:.0}%", details.state_of_health)?),
+                (
+                    "Cells",
+                    text!(
+                        "{:.2}-{:.2} V",
+                        details.min_cell_voltage, details.max_cell_voltage
+                    )?,
+                ),
+            ],
+            None => [
+                ("Voltage", text!("--")?),
+                ("Current", text!("--")?),
+                ("Temperature", text!("--")?),
+                ("Cycles", text!("--")?),
+                ("Health", text!("--")?),
+                ("Cells", text!("--")?),
+            ],
+        };
+        for (idx, (label, value)) in rows.iter().enumerate() {
+            let y = 29 + idx as i32 * 14;
+            Text::new(label, Point::new(130, y), title_style).draw(self)?;
+            Text::new(
+                value,
+                Point::new(294 - value.len() as i32 * 6, y),
+                title_style,
+            )
+            .draw(self)?;
+        }
+
+        let estimate = match status.estimate() {
+            Some(estimate) => text!("{}", estimate)?,
+            None => text!("No estimate")?,
+        };
+        Text::new(&estimate, Point::new(2, 123), title_style).draw(self)?;
+        Ok(())
+    }
+    // diagnostics in two columns, now is used for the uptime and the age of the last message
+    pub fn draw_system_page(
+        &mut self,
+        info: &system::SystemInfo,
+        now: crate::clock::Tick,
+    ) -> Result<()> {
+        self.clear(T::Color::WHITE)?;
+        let title_style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
+            .text_color(T::Color::BLACK)
+            .build();
+        let row_style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_5X8)
+            .text_color(T::Color::BLACK)
+            .build();
+        let line_style = PrimitiveStyle::with_stroke(T::Color::BLACK, 1);
+
+        Text::new("System", Point::new(2, 11), title_style).draw(self)?;
+        let version = text!("v{} {}", info.version, info.build_hash)?;
+        Text::new(
+            &version,
+            Point::new(294 - version.len() as i32 * 6, 11),
+            title_style,
+        )
+        .draw(self)?;
+        Line::new(Point::new(0, 14), Point::new(295, 14))
+            .into_styled(line_style)
+            .draw(self)?;
+        Line::new(Point::new(148, 15), Point::new(148, 127))
+            .into_styled(line_style)
+            .draw(self)?;
+
+        // 6 rows on the left, the counters on the right
+        for (idx, (label, value)) in info.rows(now).iter().enumerate() {
+            let (left, right) = if idx < 6 { (2, 145) } else { (152, 294) };
+            let y = 30 + (idx % 6) as i32 * 16;
+            Text::new(label, Point::new(left, y), row_style).draw(self)?;
+            // long values like the server address cut into the label otherwise
+            let max_chars = (right - left) as usize / 5 - label.len() - 1;
+            let end = value.char_indices().nth(max_chars).map_or(value.len(), |(i, _)| i);
+            let value = &value[..end];
+            Text::new(
+                value,
+                Point::new(right - value.chars().count() as i32 * 5, y),
+                row_style,
+            )
+            .draw(self)?;
+        }
+        Ok(())
+    }
+    // inverted bar across the top of every page, in the accent colour if the panel has one. the
+    // page has to be redrawn to remove it
+    pub fn draw_alert_banner(&mut self, msg: &str) -> Result<()> {
+        self.fill_solid(
+            &Rectangle::new(Point::new(0, 0), Size::new(296, 14)),
+            T::Color::ACCENT,
+        )?;
+        let style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
+            .text_color(T::Color::WHITE)
+            .build();
+        let text = text!("! {} !", msg)?;
+        Text::new(
+            &text,
+            Point::new(148 - text.len() as i32 * 3, 10),
+            style,
+        )
+        .draw(self)?;
+        Ok(())
+    }
+    // bar across the bottom of the pages, a warning in the accent colour. like the alerts, the
+    // page has to be redrawn to remove it
+    pub fn draw_notice_banner(&mut self, notice: &notice::ActiveNotice) -> Result<()> {
+        let fill = if notice.severity == prototypes::types::Severity::Warning {
+            T::Color::ACCENT
+        } else {
+            T::Color::BLACK
+        };
+        self.fill_solid(
+            &Rectangle::new(Point::new(0, 114), Size::new(296, 14)),
+            fill,
+        )?;
+        let style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
+            .text_color(T::Color::WHITE)
+            .build();
+        let text = notice::line(&notice.title, &notice.body);
+        Text::new(
+            &text,
+            Point::new(148 - text.chars().count() as i32 * 3, 124),
+            style,
+        )
+        .draw(self)?;
+        Ok(())
+    }
+    // a critical notice covers the page, the body is wrapped and cut after seven lines
+    pub fn draw_notice_card(&mut self, notice: &notice::ActiveNotice) -> Result<()> {
+        self.clear(T::Color::WHITE)?;
+        self.fill_solid(
+            &Rectangle::new(Point::new(0, 0), Size::new(296, 16)),
+            T::Color::ACCENT,
+        )?;
+        let title_style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
+            .text_color(T::Color::WHITE)
+            .build();
+        let body_style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
+            .text_color(T::Color::BLACK)
+            .build();
+        let title = notice::line(&notice.title, "");
+        Text::new(&title, Point::new(4, 12), title_style).draw(self)?;
+        for (idx, line) in notice::wrap(&notice.body, notice::LINE_CHARS)
+            .take(7)
+            .enumerate()
+        {
+            Text::new(line, Point::new(4, 30 + idx as i32 * 12), body_style).draw(self)?;
+        }
+        Line::new(Point::new(0, 113), Point::new(295, 113))
+            .into_styled(PrimitiveStyle::with_stroke(T::Color::BLACK, 1))
+            .draw(self)?;
+        let hint = "Press a button to close";
+        Text::new(
+            hint,
+            Point::new(148 - hint.len() as i32 * 3, 124),
+            body_style,
+        )
+        .draw(self)?;
+        Ok(())
+    }
+    // the pictures of the server, they cover the whole canvas
+    pub fn draw_screen(&mut self, screen: &bitmap::Screen) -> Result<()> {
+        let area = Rectangle::new(
+            Point::zero(),
+            Size::new(panel::CANVAS_WIDTH, panel::CANVAS_HEIGHT),
+        );
+        let colors = area.points().map(|point| {
+            if screen.is_black(point) {
+                T::Color::BLACK
+            } else {
+                T::Color::WHITE
+            }
+        });
+        self.fill_contiguous(&area, colors)?;
+        Ok(())
+    }
+    // the screen at night, only the few values worth a look in large letters. it is refreshed
+    // rarely, so the time of the values is shown as well
+    pub fn draw_night_summary(&mut self, summary: &night::NightSummary) -> Result<()> {
+        self.clear(T::Color::WHITE)?;
+        let title_style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
+            .text_color(T::Color::BLACK)
+            .build();
+        let value_style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_10X20)
+            .text_color(T::Color::BLACK)
+            .build();
+        let line_style = PrimitiveStyle::with_stroke(T::Color::BLACK, 1);
+
+        let title = text!("Night, as of {}", night::format_minute(summary.minute_of_day))?;
+        Text::new(&title, Point::new(2, 11), title_style).draw(self)?;
+        let sunrise = text!("Sunrise {}", night::format_minute(summary.sunrise))?;
+        Text::new(
+            &sunrise,
+            Point::new(294 - sunrise.len() as i32 * 6, 11),
+            title_style,
+        )
+        .draw(self)?;
+        Line::new(Point::new(0, 14), Point::new(295, 14))
+            .into_styled(line_style)
+            .draw(self)?;
+        Line::new(Point::new(0, 100), Point::new(295, 100))
+            .into_styled(line_style)
+            .draw(self)?;
+
+        // three columns, each with the value centered above its label
+        let columns = [
+            (
+                "Battery",
+                summary
+                    .soc_percent
+                    .map(|soc| text!("{:.0}%", soc))
+                    .unwrap_or(text!("--%"))?,
+            ),
+            (
+                "House",
+                summary
+                    .house_kw
+                    .map(|kw| text!("{:.2}kW", kw))
+                    .unwrap_or(text!("--"))?,
+            ),
+            (
+                "Grid",
+                summary
+                    .grid_kw
+                    .map(|kw| text!("{:+.2}kW", kw))
+                    .unwrap_or(text!("--"))?,
+            ),
+        ];
+        for (idx, (label, value)) in columns.iter().enumerate() {
+            let center = 49 + idx as i32 * 99;
+            Text::new(
+                value,
+                Point::new(center - value.len() as i32 * 5, 58),
+                value_style,
+            )
+            .draw(self)?;
+            Text::new(
+                label,
+                Point::new(center - label.len() as i32 * 3, 78),
+                title_style,
+            )
+            .draw(self)?;
+        }
+
+        let today = match (&summary.generated, &summary.consumption) {
+            (Some(generated), Some(consumption)) => {
+                text!("Today: {} kWh solar, {} kWh used", generated, consumption)?
+            }
+            _ => text!("Today: --")?,
+        };
+        Text::new(&today, Point::new(2, 118), title_style).draw(self)?;
+        Ok(())
+    }
+    // autarky and self consumption in the bottom left corner, none is shown as "--"
+    pub fn update_self_sufficiency(
+        &mut self,
+        autarky: Option<f32>,
+        self_consumption: Option<f32>,
+    ) -> Result<()> {
+        self.fill_solid(
+            &Rectangle::new(Point::new(0, 87), Size::new(53, 28)),
+            T::Color::WHITE,
+        )?;
+        let style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_4X6)
+            .text_color(T::Color::BLACK)
+            .build();
+        let rows = [("Autarky", autarky, 93), ("Self use", self_consumption, 106)];
+        for (label, value, y) in rows {
+            let text = match value {
+                Some(v) => text!("{} {:.0}%", label, v * 100.0)?,
+                None => text!("{} --%", label)?,
+            };
+            Text::new(&text, Point::new(1, y), style).draw(self)?;
+
+            // gauge below the text
+            Rectangle::new(Point::new(1, y + 3), Size::new(50, 5))
+                .into_styled(PrimitiveStyle::with_stroke(
+                    T::Color::BLACK,
+                    1,
+                ))
+                .draw(self)?;
+            if let Some(v) = value {
+                let width = (v.clamp(0.0, 1.0) * 48.0 + 0.5) as u32;
+                self.fill_solid(
+                    &Rectangle::new(Point::new(2, y + 4), Size::new(width, 3)),
+                    T::Color::BLACK,
+                )?;
+            }
+        }
+        Ok(())
+    }
+    pub fn update_sun_data(&mut self, sunrise: &str, sunset: &str) -> Result<()> {
+        let style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
+            .text_color(T::Color::BLACK)
             .build();
         if sunset.len() > 5 || sunrise.len() > 5 {
-            return Err(anyhow!("error input sequence to long"));
+            return Err(DisplayError::InvalidInput);
         }
 
         // clears both text areas
         self.fill_solid(
             &Rectangle::new(Point::new(190, 3), Size::new(30, 12)),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
         self.fill_solid(
             &Rectangle::new(Point::new(260, 3), Size::new(30, 12)),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
 
         Text::new(sunrise, Point::new(190, 12), style).draw(self)?;
@@ -1044,17 +1931,130 @@ where
         Ok(())
     }
 
-    pub fn draw_default_weather(&mut self) -> anyhow::Result<()> {
+    // replaces the weather grid with a bar per hour. the cheapest window is drawn solid, past
+    // hours only as their top and the current hour is marked above its bar
+    pub fn update_price_forecast(&mut self, prices: &price::PriceCache) -> Result<()> {
+        self.fill_solid(
+            &Rectangle::new(Point::new(150, 17), Size::new(146, 52)),
+            T::Color::WHITE,
+        )?;
+        let desc_text_style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_4X6)
+            .text_color(T::Color::BLACK)
+            .build();
+
+        let now = match prices.current_price() {
+            Some(price) => text!("Now {:.1}ct", price)?,
+            None => text!("Now --")?,
+        };
+        Text::new(&now, Point::new(152, 23), desc_text_style).draw(self)?;
+        let window = prices.cheapest_window(price::DEFAULT_WINDOW_HOURS);
+        if let Some(window) = window {
+            let low = text!(
+                "Low {:02}-{:02}h {:.1}ct",
+                window.start_hour % 24,
+                (window.start_hour + window.hours) % 24,
+                window.average
+            )?;
+            let x = 295 - low.len() as i32 * 4;
+            Text::new(&low, Point::new(x, 23), desc_text_style).draw(self)?;
+        }
+
+        let values = prices.prices();
+        if values.is_empty() {
+            return Ok(());
+        }
+        let start_x = 151;
+        let top = 28;
+        let bottom = 66;
+        let bar_width = (144 / values.len()).max(1) as i32;
+        // negative prices hang below the zero line
+        let high = values.iter().fold(0.0f32, |acc, v| acc.max(*v));
+        let low = values.iter().fold(0.0f32, |acc, v| acc.min(*v));
+        let range = if high - low > 0.0 { high - low } else { 1.0 };
+        let to_y = |v: f32| top + ((high - v) / range * (bottom - top) as f32) as i32;
+        let zero_y = to_y(0.0);
+
+        let current = prices.current_hour();
+        for (hour, &value) in values.iter().enumerate() {
+            let x = start_x + hour as i32 * bar_width;
+            let y = to_y(value);
+            let (from, to) = if y < zero_y { (y, zero_y) } else { (zero_y, y) };
+            let width = (bar_width - 1).max(1);
+            let in_window = window
+                .map(|w| (w.start_hour..w.start_hour + w.hours).contains(&hour))
+                .unwrap_or(false);
+            if current.is_some_and(|current| hour < current) {
+                self.fill_solid(
+                    &Rectangle::new(Point::new(x, y), Size::new(width as u32, 1)),
+                    T::Color::BLACK,
+                )?;
+            } else if in_window {
+                self.fill_solid(
+                    &Rectangle::new(
+                        Point::new(x, from),
+                        Size::new(width as u32, (to - from + 1) as u32),
+                    ),
+                    T::Color::BLACK,
+                )?;
+            } else {
+                (x..x + width)
+                    .flat_map(|px| (from..=to).map(move |py| Point::new(px, py)))
+                    .filter(|point| point.y == y || (point.x + point.y) % 2 == 0)
+                    .map(|point| Pixel(point, T::Color::BLACK))
+                    .draw(self)?;
+            }
+            if current == Some(hour) {
+                let center = x + width / 2;
+                Line::new(Point::new(center - 1, 25), Point::new(center + 1, 25))
+                    .into_styled(PrimitiveStyle::with_stroke(T::Color::BLACK, 1))
+                    .draw(self)?;
+                Pixel(Point::new(center, 26), T::Color::BLACK).draw(self)?;
+            }
+        }
+        // zero line, and where tomorrow starts
+        Line::new(Point::new(start_x, zero_y), Point::new(start_x + 143, zero_y))
+            .into_styled(PrimitiveStyle::with_stroke(T::Color::BLACK, 1))
+            .draw(self)?;
+        if values.len() > 24 {
+            let x = start_x + 24 * bar_width - 1;
+            self.draw_dashed_line(Point::new(x, top), Point::new(x, bottom))?;
+        }
+        Ok(())
+    }
+    // brings the weather grid back once there are no prices left
+    pub fn clear_price_forecast(&mut self) -> Result<()> {
+        self.fill_solid(
+            &Rectangle::new(Point::new(150, 17), Size::new(146, 52)),
+            T::Color::WHITE,
+        )?;
+        self.draw_default_weather_grid()
+    }
+
+    // the lines between the power flow, the weather and the chart
+    pub fn draw_dashboard_frame(&mut self) -> Result<()> {
+        Line::new(Point::new(149, 0), Point::new(149, 128))
+            .into_styled(
+                PrimitiveStyleBuilder::new()
+                    .stroke_color(T::Color::BLACK)
+                    .stroke_width(2)
+                    .build(),
+            )
+            .draw(self)?;
         // first split display on the right into 2;
 
         Line::new(Point::new(149, 70), Point::new(296, 70))
             .into_styled(
                 PrimitiveStyleBuilder::new()
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .stroke_width(2)
                     .build(),
             )
             .draw(self)?;
+        Ok(())
+    }
+    // the icons of sunrise and sunset with placeholder times
+    pub fn draw_default_sun(&mut self) -> Result<()> {
         SUN_PATTERN
             .iter()
             .enumerate()
@@ -1063,11 +2063,11 @@ where
                 let y = 2 + (idx / 18);
                 let color = {
                     if num == &0 {
-                        epd_waveshare::color::Color::White
+                        T::Color::WHITE
                     } else if num == &1 {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     } else {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     }
                 };
                 Pixel(Point::new(x as i32, y as i32), color)
@@ -1085,11 +2085,11 @@ where
                 let y = 2 + (idx / 18);
                 let color = {
                     if num == &0 {
-                        epd_waveshare::color::Color::White
+                        T::Color::WHITE
                     } else if num == &1 {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     } else {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     }
                 };
                 Pixel(Point::new(x as i32, y as i32), color)
@@ -1099,12 +2099,20 @@ where
         self.draw_arrow_simple(SimpleArrowDirection::Down, (248, 3))?;
         let style = MonoTextStyleBuilder::new()
             .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
-            .text_color(epd_waveshare::color::Color::Black)
+            .text_color(T::Color::BLACK)
             .build();
 
         // sunset and sunrise values
         Text::new("00.00", Point::new(190, 12), style).draw(self)?;
         Text::new("00.00", Point::new(260, 12), style).draw(self)?;
+        Ok(())
+    }
+    // icons and placeholder values of the hourly weather below the sun data
+    pub fn draw_default_weather_grid(&mut self) -> Result<()> {
+        let style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
+            .text_color(T::Color::BLACK)
+            .build();
 
         // other descriptors
         RAINDROPS
@@ -1115,11 +2123,11 @@ where
                 let y = 15 + (idx / 18);
                 let color = {
                     if num == &0 {
-                        epd_waveshare::color::Color::White
+                        T::Color::WHITE
                     } else if num == &1 {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     } else {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     }
                 };
                 Pixel(Point::new(x as i32, y as i32), color)
@@ -1133,11 +2141,11 @@ where
                 let y = 28 + (idx / 18);
                 let color = {
                     if num == &0 {
-                        epd_waveshare::color::Color::White
+                        T::Color::WHITE
                     } else if num == &1 {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     } else {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     }
                 };
                 Pixel(Point::new(x as i32, y as i32), color)
@@ -1152,11 +2160,11 @@ where
                 let y = 52 + (idx / 18);
                 let color = {
                     if num == &0 {
-                        epd_waveshare::color::Color::White
+                        T::Color::WHITE
                     } else if num == &1 {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     } else {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     }
                 };
                 Pixel(Point::new(x as i32, y as i32), color)
@@ -1170,7 +2178,7 @@ where
             .into_styled(
                 PrimitiveStyleBuilder::new()
                     .stroke_width(1)
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .build(),
             )
             .draw(self)?;
@@ -1179,7 +2187,7 @@ where
             .into_styled(
                 PrimitiveStyleBuilder::new()
                     .stroke_width(1)
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .build(),
             )
             .draw(self)?;
@@ -1188,7 +2196,7 @@ where
             .into_styled(
                 PrimitiveStyleBuilder::new()
                     .stroke_width(1)
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .build(),
             )
             .draw(self)?;
@@ -1202,10 +2210,10 @@ where
         uv: &'a str,
         temp: &'a str,
         x_offset: i32,
-    ) -> anyhow::Result<()> {
+    ) -> Result<()> {
         let style_2 = MonoTextStyleBuilder::new()
             .font(&embedded_graphics::mono_font::ascii::FONT_5X8)
-            .text_color(epd_waveshare::color::Color::Black)
+            .text_color(T::Color::BLACK)
             .build();
         if rain.len() == 3 {
             Text::new(rain, Point::new(155 + x_offset + 10, 27), style_2).draw(self)?;
@@ -1238,29 +2246,29 @@ where
 
         Ok(())
     }
-    pub fn update_weather_data(&mut self, weather_data: prototypes::types::HourlyNew) -> anyhow::Result<()> {
+    pub fn update_weather_data(&mut self, weather_data: &prototypes::types::HourlyNew) -> Result<()> {
         let offsets = &[20, 50, 80, 110];
         self.fill_solid(
             &Rectangle::new(Point::new(172, 18), Size::new(130, 50)),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
         for (idx, x_offset) in offsets.iter().enumerate() {
             let rain = weather_data
                 .rain
                 .get(idx)
-                .ok_or(anyhow!("error missing data"))?;
+                .ok_or(DisplayError::MissingData)?;
             let cloud = weather_data
                 .cloud_cover
                 .get(idx)
-                .ok_or(anyhow!("error missing data"))?;
+                .ok_or(DisplayError::MissingData)?;
             let uv = weather_data
                 .uv_index
                 .get(idx)
-                .ok_or(anyhow!("error missing data"))?;
+                .ok_or(DisplayError::MissingData)?;
             let temp = weather_data
                 .temperature_2m
                 .get(idx)
-                .ok_or(anyhow!("error missing data"))?;
+                .ok_or(DisplayError::MissingData)?;
 
             self.draw_row_weather_data(rain, cloud, uv, temp, x_offset.clone())?
         }
@@ -1270,7 +2278,7 @@ where
             .into_styled(
                 PrimitiveStyleBuilder::new()
                     .stroke_width(1)
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .build(),
             )
             .draw(self)?;
@@ -1279,7 +2287,7 @@ where
             .into_styled(
                 PrimitiveStyleBuilder::new()
                     .stroke_width(1)
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .build(),
             )
             .draw(self)?;
@@ -1287,7 +2295,7 @@ where
             .into_styled(
                 PrimitiveStyleBuilder::new()
                     .stroke_width(1)
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .build(),
             )
             .draw(self)?;
@@ -1298,7 +2306,7 @@ where
         &mut self,
         direction: SimpleArrowDirection,
         startpos: (i32, i32),
-    ) -> anyhow::Result<()> {
+    ) -> Result<()> {
         match direction {
             SimpleArrowDirection::Up => {
                 Line::new(
@@ -1308,7 +2316,7 @@ where
                 .into_styled(
                     PrimitiveStyleBuilder::new()
                         .stroke_width(2)
-                        .stroke_color(epd_waveshare::color::Color::Black)
+                        .stroke_color(T::Color::BLACK)
                         .build(),
                 )
                 .draw(self)?;
@@ -1319,7 +2327,7 @@ where
                 .into_styled(
                     PrimitiveStyleBuilder::new()
                         .stroke_width(2)
-                        .stroke_color(epd_waveshare::color::Color::Black)
+                        .stroke_color(T::Color::BLACK)
                         .build(),
                 )
                 .draw(self)?;
@@ -1330,11 +2338,11 @@ where
                 .into_styled(
                     PrimitiveStyleBuilder::new()
                         .stroke_width(2)
-                        .stroke_color(epd_waveshare::color::Color::Black)
+                        .stroke_color(T::Color::BLACK)
                         .build(),
                 )
                 .draw(self)?;
-                Pixel(Point::new(startpos.0 - 6, startpos.1 + 6), epd_waveshare::color::Color::Black).draw(self)?;
+                Pixel(Point::new(startpos.0 - 6, startpos.1 + 6), T::Color::BLACK).draw(self)?;
             }
             SimpleArrowDirection::Down => {
                 Line::new(
@@ -1344,7 +2352,7 @@ where
                 .into_styled(
                     PrimitiveStyleBuilder::new()
                         .stroke_width(2)
-                        .stroke_color(epd_waveshare::color::Color::Black)
+                        .stroke_color(T::Color::BLACK)
                         .build(),
                 )
                 .draw(self)?;
@@ -1355,7 +2363,7 @@ where
                 .into_styled(
                     PrimitiveStyleBuilder::new()
                         .stroke_width(2)
-                        .stroke_color(epd_waveshare::color::Color::Black)
+                        .stroke_color(T::Color::BLACK)
                         .build(),
                 )
                 .draw(self)?;
@@ -1366,14 +2374,14 @@ where
                 .into_styled(
                     PrimitiveStyleBuilder::new()
                         .stroke_width(2)
-                        .stroke_color(epd_waveshare::color::Color::Black
+                        .stroke_color(T::Color::BLACK
                             )
                         .build(),
                 )
                 .draw(self)?;
                 Pixel(
                     Point::new(startpos.0 + 7, startpos.1 + 11 - 6),
-                    epd_waveshare::color::Color::Black,
+                    T::Color::BLACK,
                 )
                 .draw(self)?;
             }
@@ -1381,32 +2389,32 @@ where
 
         Ok(())
     }
-    pub fn draw_status_message(&mut self, msg: &str) -> anyhow::Result<()> {
+    pub fn draw_status_message(&mut self, msg: &str) -> Result<()> {
         let style = MonoTextStyleBuilder::new()
             .font(&embedded_graphics::mono_font::ascii::FONT_9X15)
-            .text_color(epd_waveshare::color::Color::Black)
+            .text_color(T::Color::BLACK)
             .build();
         Text::new(msg, Point::new(58, 64), style).draw(self)?;
         Ok(())
     }
-    pub fn clear_status_message(&mut self) -> anyhow::Result<()> {
+    pub fn clear_status_message(&mut self) -> Result<()> {
         self.fill_solid(
             &Rectangle::new(
                 Point::new(58, 50),
                 embedded_graphics::prelude::Size::new(180, 20),
             ),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
         Ok(())
     }
-    fn _draw_default_total(&mut self) -> anyhow::Result<()> {
+    fn _draw_default_total(&mut self) -> Result<()> {
         let style = MonoTextStyleBuilder::new()
             .font(&embedded_graphics::mono_font::ascii::FONT_9X15)
-            .text_color(epd_waveshare::color::Color::Black)
+            .text_color(T::Color::BLACK)
             .build();
         let style_total = MonoTextStyleBuilder::new()
             .font(&embedded_graphics::mono_font::ascii::FONT_6X12)
-            .text_color(epd_waveshare::color::Color::Black)
+            .text_color(T::Color::BLACK)
             .build();
 
         // top right corner display "Total"
@@ -1415,7 +2423,7 @@ where
             .into_styled(
                 PrimitiveStyleBuilder::new()
                     .stroke_width(2)
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .build(),
             )
             .draw(self)?;
@@ -1423,7 +2431,7 @@ where
             .into_styled(
                 PrimitiveStyleBuilder::new()
                     .stroke_width(2)
-                    .stroke_color(epd_waveshare::color::Color::Black)
+                    .stroke_color(T::Color::BLACK)
                     .build(),
             )
             .draw(self)?;
@@ -1437,11 +2445,11 @@ where
                 let y = 90 + (idx / 18);
                 let color = {
                     if num == &0 {
-                        epd_waveshare::color::Color::White
+                        T::Color::WHITE
                     } else if num == &1 {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     } else {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     }
                 };
                 Pixel(Point::new(x as i32, y as i32), color)
@@ -1457,11 +2465,11 @@ where
                 let y = 110 + (idx / 18);
                 let color = {
                     if num == &0 {
-                        epd_waveshare::color::Color::White
+                        T::Color::WHITE
                     } else if num == &1 {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     } else {
-                        epd_waveshare::color::Color::Black
+                        T::Color::BLACK
                     }
                 };
                 Pixel(Point::new(x as i32, y as i32), color)
@@ -1475,38 +2483,105 @@ where
         &mut self,
         consumption: &str,
         generated: &str,
-    ) -> anyhow::Result<()> {
+    ) -> Result<()> {
         if consumption.len() > 5 || generated.len() > 5 {
-            return Err(anyhow!("input to long"));
+            return Err(DisplayError::InvalidInput);
         }
         let style = MonoTextStyleBuilder::new()
             .font(&embedded_graphics::mono_font::ascii::FONT_9X15)
-            .text_color(epd_waveshare::color::Color::Black)
+            .text_color(T::Color::BLACK)
             .build();
         self.fill_solid(
             &Rectangle::new(Point::new(205, 90), Size::new(45, 12)),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
         self.fill_solid(
             &Rectangle::new(Point::new(205, 110), Size::new(45, 12)),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
 
         Text::new(generated, Point::new(205, 100), style).draw(self)?;
         Text::new(consumption, Point::new(205, 120), style).draw(self)?;
         Ok(())
     }
-    pub fn update_chart(&mut self, data: &[f32]) -> anyhow::Result<()> {
+    pub fn update_chart(
+        &mut self,
+        data: &[f32],
+        actual: &history::ActualSeries,
+        options: ChartOptions,
+    ) -> Result<()> {
         self.fill_solid(
-            &Rectangle::new(Point::new(142, 76), Size::new(30, 5)),
-            epd_waveshare::color::Color::White,
+            &Rectangle::new(Point::new(151, 75), Size::new(146, 47)),
+            T::Color::WHITE,
         )?;
+       self.draw_chart(data, actual, options)?;
+
+        Ok(())
+    }
+    // shown instead of the chart if the prediction could not be used
+    pub fn draw_chart_status(&mut self, msg: &str) -> Result<()> {
         self.fill_solid(
             &Rectangle::new(Point::new(151, 75), Size::new(146, 47)),
-            epd_waveshare::color::Color::White,
+            T::Color::WHITE,
         )?;
-       self.draw_chart(data)?;
-
+        let style = MonoTextStyleBuilder::new()
+            .font(&embedded_graphics::mono_font::ascii::FONT_5X8)
+            .text_color(T::Color::BLACK)
+            .build();
+        Text::new(msg, Point::new(158, 100), style).draw(self)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct NullTarget;
+
+    impl DrawTarget for NullTarget {
+        type Color = epd_waveshare::color::Color;
+        type Error = core::convert::Infallible;
+        fn draw_iter<I>(&mut self, _pixels: I) -> core::result::Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            Ok(())
+        }
+    }
+
+    impl OriginDimensions for NullTarget {
+        fn size(&self) -> Size {
+            Size::new(296, 128)
+        }
+    }
+
+    #[test]
+    fn the_wallbox_state_is_cut_at_characters() {
+        use crate::prototypes::types::WallboxNew;
+
+        let mut display = DisplayBoxed::new(NullTarget);
+        // the tenth byte is inside the ü
+        for state in ["geplant für 22:00", "Lädt gerade", "Lädt"] {
+            let wallbox = WallboxNew {
+                present: true,
+                state: state.to_string(),
+                car_connected: true,
+                ..Default::default()
+            };
+            display.update_wallbox(Some(&wallbox)).unwrap();
+        }
+    }
+
+    #[test]
+    fn values_that_do_not_fit_are_invalid_input() {
+        let mut display = DisplayBoxed::new(NullTarget);
+        let err = display.update_battery_percentage("1000").unwrap_err();
+        assert_eq!(err, DisplayError::InvalidInput);
+        assert!(err.is_recoverable());
+        assert_eq!(
+            display.update_total_display("123456", "1.0"),
+            Err(DisplayError::InvalidInput)
+        );
+    }
+}
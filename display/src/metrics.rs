@@ -0,0 +1,147 @@
+// self sufficiency of the house. the totals of the day come from the server, the grid flows
+// are only sent as the current power, so they are integrated on the client
+
+// samples further apart than this are not integrated, e.g. after a reconnect
+pub(crate) const MAX_SAMPLE_GAP_SECS: f32 = 15.0 * 60.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct GridEnergy {
+    pub imported_kwh: f32,
+    pub exported_kwh: f32,
+}
+
+impl GridEnergy {
+    // grid_kw is positive when importing and negative when exporting
+    pub fn add_sample(&mut self, grid_kw: f32, elapsed_secs: f32) {
+        if !grid_kw.is_finite() || !(0.0..=MAX_SAMPLE_GAP_SECS).contains(&elapsed_secs) {
+            return;
+        }
+        let kwh = grid_kw * elapsed_secs / 3600.0;
+        if kwh > 0.0 {
+            self.imported_kwh += kwh;
+        } else {
+            self.exported_kwh -= kwh;
+        }
+    }
+}
+
+// share of the consumption that was not covered by the grid
+pub fn autarky(consumption_kwh: f32, imported_kwh: f32) -> Option<f32> {
+    ratio(consumption_kwh, imported_kwh)
+}
+
+// share of the generation that was used in the house or stored in the battery
+pub fn self_consumption(generated_kwh: f32, exported_kwh: f32) -> Option<f32> {
+    ratio(generated_kwh, exported_kwh)
+}
+
+fn ratio(total: f32, external: f32) -> Option<f32> {
+    if !total.is_finite() || !external.is_finite() || total <= 0.0 {
+        return None;
+    }
+    Some(((total - external) / total).clamp(0.0, 1.0))
+}
+
+#[derive(Debug, Default)]
+pub struct EnergyBalance {
+    grid: GridEnergy,
+    consumption_kwh: Option<f32>,
+    generated_kwh: Option<f32>,
+    last_minute: Option<u16>,
+}
+
+impl EnergyBalance {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn record_grid(&mut self, minute_of_day: u16, grid_kw: f32, elapsed_secs: f32) {
+        if let Some(last) = self.last_minute {
+            // the clock jumped back by more than an hour, so a new day has started
+            if minute_of_day + 60 < last {
+                *self = Self::default();
+            }
+        }
+        self.last_minute = Some(minute_of_day);
+        self.grid.add_sample(grid_kw, elapsed_secs);
+    }
+    // totals of the day as sent in TotalDataNew
+    pub fn set_totals(&mut self, consumption: &str, generated: &str) {
+        self.consumption_kwh = consumption.trim().parse().ok();
+        self.generated_kwh = generated.trim().parse().ok();
+    }
+    pub fn grid(&self) -> GridEnergy {
+        self.grid
+    }
+    pub fn autarky(&self) -> Option<f32> {
+        autarky(self.consumption_kwh?, self.grid.imported_kwh)
+    }
+    pub fn self_consumption(&self) -> Option<f32> {
+        self_consumption(self.generated_kwh?, self.grid.exported_kwh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ratios() {
+        assert_eq!(autarky(10.0, 2.5), Some(0.75));
+        assert_eq!(self_consumption(20.0, 15.0), Some(0.25));
+        assert_eq!(autarky(10.0, 0.0), Some(1.0));
+        // the integrated grid flows can be slightly off from the totals of the server
+        assert_eq!(autarky(10.0, 12.0), Some(0.0));
+    }
+
+    #[test]
+    fn ratios_without_totals() {
+        assert_eq!(autarky(0.0, 1.0), None);
+        assert_eq!(self_consumption(0.0, 0.0), None);
+        assert_eq!(autarky(f32::NAN, 1.0), None);
+    }
+
+    #[test]
+    fn grid_flows_are_integrated() {
+        let mut grid = GridEnergy::default();
+        grid.add_sample(2.0, 900.0);
+        grid.add_sample(2.0, 900.0);
+        grid.add_sample(-4.0, 900.0);
+        grid.add_sample(0.0, 60.0);
+        assert_eq!(
+            grid,
+            GridEnergy {
+                imported_kwh: 1.0,
+                exported_kwh: 1.0,
+            }
+        );
+    }
+
+    #[test]
+    fn large_gaps_are_skipped() {
+        let mut grid = GridEnergy::default();
+        grid.add_sample(2.0, 3600.0);
+        grid.add_sample(2.0, -1.0);
+        assert_eq!(grid, GridEnergy::default());
+    }
+
+    #[test]
+    fn balance_combines_totals_and_grid() {
+        let mut balance = EnergyBalance::new();
+        assert_eq!(balance.autarky(), None);
+        balance.set_totals("8.00", "8.00");
+        balance.record_grid(600, 4.0, 900.0);
+        balance.record_grid(720, -6.0, 600.0);
+        assert_eq!(balance.autarky(), Some(0.875));
+        assert_eq!(balance.self_consumption(), Some(0.875));
+    }
+
+    #[test]
+    fn balance_resets_on_a_new_day() {
+        let mut balance = EnergyBalance::new();
+        balance.set_totals("8.00", "12.00");
+        balance.record_grid(1430, 4.0, 900.0);
+        balance.record_grid(5, 4.0, 0.0);
+        assert_eq!(balance.grid(), GridEnergy::default());
+        assert_eq!(balance.autarky(), None);
+    }
+}
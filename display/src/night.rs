@@ -0,0 +1,322 @@
+// night mode. between sunset and sunrise, or during fixed quiet hours, the clients show a small
+// summary instead of the pages and refresh it far less often, the panel sleeps in between. the
+// time of day comes from the ts of the messages and is moved forward with the monotonic clock,
+// so the night also ends while the server is quiet
+use alloc::string::String;
+use core::time::Duration;
+
+use core::fmt::Write;
+
+use crate::clock::Tick;
+use crate::history;
+use crate::prototypes::types::NewUiStruct;
+use crate::TextBuffer;
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NightError {
+    InvalidQuietHours,
+    InvalidRefresh,
+}
+
+impl NightError {
+    pub fn label(&self) -> &'static str {
+        match self {
+            NightError::InvalidQuietHours => "invalid quiet hours",
+            NightError::InvalidRefresh => "invalid night refresh",
+        }
+    }
+}
+
+impl core::fmt::Display for NightError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl core::error::Error for NightError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Schedule {
+    // from sunset to sunrise of the daily weather
+    #[default]
+    Sun,
+    // minutes of the day, an end before the start wraps around midnight
+    Quiet {
+        start_minute: u16,
+        end_minute: u16,
+    },
+    Off,
+}
+
+impl Schedule {
+    // "sun" or nothing, "off" or quiet hours like "22:00-06:30"
+    pub fn parse(schedule: &str) -> Result<Self, NightError> {
+        match schedule.trim() {
+            "" | "sun" => Ok(Schedule::Sun),
+            "off" => Ok(Schedule::Off),
+            hours => {
+                let (start, end) = hours.split_once('-').ok_or(NightError::InvalidQuietHours)?;
+                let minute = |time: &str| {
+                    history::parse_minute_of_day(time.trim()).ok_or(NightError::InvalidQuietHours)
+                };
+                Ok(Schedule::Quiet {
+                    start_minute: minute(start)?,
+                    end_minute: minute(end)?,
+                })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NightConfig {
+    pub schedule: Schedule,
+    // how often the summary is refreshed at night
+    pub refresh: Duration,
+    // how long the pages stay after a button press at night
+    pub wake: Duration,
+}
+
+impl Default for NightConfig {
+    fn default() -> Self {
+        NightConfig {
+            schedule: Schedule::Sun,
+            refresh: Duration::from_secs(30 * 60),
+            wake: Duration::from_secs(2 * 60),
+        }
+    }
+}
+
+impl NightConfig {
+    // the refresh is given in minutes
+    pub fn parse(schedule: &str, refresh: &str) -> Result<Self, NightError> {
+        let mut config = NightConfig {
+            schedule: Schedule::parse(schedule)?,
+            ..Default::default()
+        };
+        if !refresh.trim().is_empty() {
+            let secs = match refresh.trim().parse::<u64>() {
+                Ok(minutes) if minutes > 0 => minutes.checked_mul(60),
+                _ => None,
+            };
+            config.refresh = Duration::from_secs(secs.ok_or(NightError::InvalidRefresh)?);
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct NightMode {
+    config: NightConfig,
+    // minute of the day of the last ts and when it arrived
+    clock: Option<(u16, Tick)>,
+    sunrise: Option<u16>,
+    sunset: Option<u16>,
+    woken_at: Option<Tick>,
+    last_refresh: Option<Tick>,
+    active: bool,
+}
+
+impl NightMode {
+    pub fn new(config: NightConfig) -> Self {
+        NightMode {
+            config,
+            ..Default::default()
+        }
+    }
+    pub fn config(&self) -> &NightConfig {
+        &self.config
+    }
+    pub fn set_config(&mut self, config: NightConfig) {
+        self.config = config;
+    }
+    pub fn set_clock(&mut self, minute_of_day: u16, now: Tick) {
+        self.clock = Some((minute_of_day, now));
+    }
+    // times like "06:12", invalid ones are ignored and the last valid ones are kept
+    pub fn set_sun(&mut self, sunrise: &str, sunset: &str) {
+        if let Some(sunrise) = history::parse_minute_of_day(sunrise) {
+            self.sunrise = Some(sunrise);
+        }
+        if let Some(sunset) = history::parse_minute_of_day(sunset) {
+            self.sunset = Some(sunset);
+        }
+    }
+    pub fn sunrise(&self) -> Option<u16> {
+        self.sunrise
+    }
+    pub fn minute_of_day(&self, now: Tick) -> Option<u16> {
+        let (minute, at) = self.clock?;
+        let elapsed = (now.duration_since(at).as_secs() / 60 % MINUTES_PER_DAY as u64) as u16;
+        Some((minute + elapsed) % MINUTES_PER_DAY)
+    }
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+    // returns true if the night started or ended
+    pub fn update(&mut self, now: Tick) -> bool {
+        let woken = self
+            .woken_at
+            .is_some_and(|at| now.duration_since(at) < self.config.wake);
+        let quiet = self
+            .minute_of_day(now)
+            .is_some_and(|minute| self.is_quiet(minute));
+        let active = quiet && !woken;
+        if active == self.active {
+            return false;
+        }
+        self.active = active;
+        self.last_refresh = None;
+        true
+    }
+    // a button was pressed, the pages stay for a while even at night
+    pub fn wake(&mut self, now: Tick) -> bool {
+        self.woken_at = Some(now);
+        self.update(now)
+    }
+    // the first refresh of a night is always due
+    pub fn refresh_due(&self, now: Tick) -> bool {
+        self.active
+            && self
+                .last_refresh
+                .is_none_or(|last| now.duration_since(last) >= self.config.refresh)
+    }
+    pub fn refreshed(&mut self, now: Tick) {
+        self.last_refresh = Some(now);
+    }
+    fn is_quiet(&self, minute: u16) -> bool {
+        match self.config.schedule {
+            Schedule::Sun => match (self.sunrise, self.sunset) {
+                (Some(sunrise), Some(sunset)) => minute < sunrise || minute >= sunset,
+                _ => false,
+            },
+            Schedule::Quiet {
+                start_minute,
+                end_minute,
+            } => {
+                if start_minute <= end_minute {
+                    (start_minute..end_minute).contains(&minute)
+                } else {
+                    minute >= start_minute || minute < end_minute
+                }
+            }
+            Schedule::Off => false,
+        }
+    }
+}
+
+// what the night screen shows, borrowed from the merged data
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NightSummary<'a> {
+    pub minute_of_day: Option<u16>,
+    pub sunrise: Option<u16>,
+    pub soc_percent: Option<f32>,
+    pub house_kw: Option<f32>,
+    pub grid_kw: Option<f32>,
+    // kWh of the day as sent by the server
+    pub generated: Option<&'a str>,
+    pub consumption: Option<&'a str>,
+}
+
+impl<'a> NightSummary<'a> {
+    pub fn new(ui: &'a NewUiStruct, night: &NightMode, now: Tick) -> Self {
+        let value = |v: &Option<String>| v.as_deref().and_then(|v| v.trim().parse::<f32>().ok());
+        NightSummary {
+            minute_of_day: night.minute_of_day(now),
+            sunrise: night.sunrise(),
+            soc_percent: value(&ui.gui_bat_data_fuel_charge),
+            house_kw: value(&ui.gui_house_pow),
+            grid_kw: value(&ui.gui_grid_pow),
+            generated: ui.total_data.as_ref().map(|t| t.generated.as_str()),
+            consumption: ui.total_data.as_ref().map(|t| t.consumption.as_str()),
+        }
+    }
+}
+
+pub fn format_minute(minute: Option<u16>) -> TextBuffer {
+    let mut text = TextBuffer::new();
+    // five characters always fit
+    let _ = match minute {
+        Some(minute) => write!(text, "{:02}:{:02}", minute / 60, minute % 60),
+        None => text.write_str("--:--"),
+    };
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn night_at(schedule: Schedule, minute: u16, now: Tick) -> NightMode {
+        let mut night = NightMode::new(NightConfig {
+            schedule,
+            ..Default::default()
+        });
+        night.set_sun("06:30", "20:15");
+        night.set_clock(minute, now);
+        night
+    }
+
+    #[test]
+    fn parses_schedules() {
+        assert_eq!(Schedule::parse(""), Ok(Schedule::Sun));
+        assert_eq!(Schedule::parse("off"), Ok(Schedule::Off));
+        assert_eq!(
+            Schedule::parse("22:00-06:30"),
+            Ok(Schedule::Quiet {
+                start_minute: 22 * 60,
+                end_minute: 6 * 60 + 30,
+            })
+        );
+        assert_eq!(Schedule::parse("22:00"), Err(NightError::InvalidQuietHours));
+        assert_eq!(
+            NightConfig::parse("sun", "0"),
+            Err(NightError::InvalidRefresh)
+        );
+        assert_eq!(
+            NightConfig::parse("sun", "999999999999999999"),
+            Err(NightError::InvalidRefresh)
+        );
+        assert_eq!(
+            NightConfig::parse("sun", "60").unwrap().refresh,
+            Duration::from_secs(3600)
+        );
+    }
+
+    #[test]
+    fn follows_the_sun_with_the_clock_moving_on() {
+        let start = Tick::default();
+        let mut night = night_at(Schedule::Sun, 20 * 60, start);
+        assert!(!night.update(start));
+        // no message since, but the sun has set
+        assert!(night.update(start + Duration::from_secs(20 * 60)));
+        assert!(night.is_active());
+        assert!(night.refresh_due(start));
+        night.refreshed(start);
+        assert!(!night.refresh_due(start + Duration::from_secs(60)));
+        // sunrise the next morning
+        night.set_clock(6 * 60 + 29, start);
+        assert!(!night.update(start));
+        assert!(night.update(start + Duration::from_secs(60)));
+        assert!(!night.is_active());
+    }
+
+    #[test]
+    fn quiet_hours_and_waking_up() {
+        let start = Tick::default();
+        let quiet = Schedule::parse("22:00-06:00").unwrap();
+        let mut night = night_at(quiet, 23 * 60, start);
+        assert!(night.update(start));
+        // a press shows the pages for a while
+        assert!(night.wake(start));
+        assert!(!night.is_active());
+        assert!(!night.update(start + Duration::from_secs(60)));
+        assert!(night.update(start + Duration::from_secs(2 * 60)));
+        assert!(night.is_active());
+        // the time of day is unknown before the first message
+        let mut night = NightMode::new(NightConfig::default());
+        assert!(!night.update(start));
+    }
+}
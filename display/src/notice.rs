@@ -0,0 +1,184 @@
+// announcements of the server, like a planned power cut or the maintenance of the inverter.
+// info and warnings are a banner at the bottom of the pages, critical notices a card over the
+// whole screen. a notice stays until it expires or a button is pressed, a new one replaces it
+use alloc::string::{String, ToString};
+use core::time::Duration;
+
+use crate::clock::Tick;
+use crate::prototypes::types::{Notice, Severity};
+use crate::TextBuffer;
+
+// the 6px font fits this many characters between the margins
+pub const LINE_CHARS: usize = 47;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoticeError {
+    Empty,
+}
+
+impl NoticeError {
+    pub fn label(&self) -> &'static str {
+        match self {
+            NoticeError::Empty => "empty notice",
+        }
+    }
+}
+
+impl core::fmt::Display for NoticeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl core::error::Error for NoticeError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveNotice {
+    pub title: String,
+    pub body: String,
+    pub severity: Severity,
+    expires: Option<Tick>,
+}
+
+impl ActiveNotice {
+    pub fn is_card(&self) -> bool {
+        self.severity == Severity::Critical
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Notices {
+    active: Option<ActiveNotice>,
+}
+
+impl Notices {
+    pub fn show(&mut self, notice: &Notice, now: Tick) -> Result<(), NoticeError> {
+        let (title, body) = (notice.title.trim(), notice.body.trim());
+        if title.is_empty() && body.is_empty() {
+            return Err(NoticeError::Empty);
+        }
+        self.active = Some(ActiveNotice {
+            title: title.to_string(),
+            body: body.to_string(),
+            // unknown severities of newer servers are shown as info
+            severity: notice.severity(),
+            expires: notice
+                .expires_secs
+                .map(|secs| now + Duration::from_secs(secs.into())),
+        });
+        Ok(())
+    }
+    // returns true if there was a notice to close
+    pub fn dismiss(&mut self) -> bool {
+        self.active.take().is_some()
+    }
+    // returns true if the notice expired
+    pub fn update(&mut self, now: Tick) -> bool {
+        let expired = self
+            .active
+            .as_ref()
+            .and_then(|notice| notice.expires)
+            .is_some_and(|expires| now >= expires);
+        if expired {
+            self.active = None;
+        }
+        expired
+    }
+    pub fn active(&self) -> Option<&ActiveNotice> {
+        self.active.as_ref()
+    }
+}
+
+// plain text frames of the server are shown as info without a title
+pub fn from_text(text: &str) -> Notice {
+    Notice {
+        body: text.to_string(),
+        ..Default::default()
+    }
+}
+
+// title and body on a single line, cut at the end. it is put together on the stack, so the
+// notice is drawn without the heap
+pub fn line(title: &str, body: &str) -> TextBuffer {
+    let separator = if title.is_empty() || body.is_empty() {
+        ""
+    } else {
+        ": "
+    };
+    let mut line = TextBuffer::new();
+    for c in title.chars().chain(separator.chars()).chain(body.chars()) {
+        // only characters outside of ascii can fill the buffer before the line is full
+        if line.chars().count() == LINE_CHARS || line.push(c).is_err() {
+            break;
+        }
+    }
+    line
+}
+
+// breaks the text into lines at the spaces, words longer than a line are cut
+pub fn wrap(text: &str, width: usize) -> impl Iterator<Item = &str> {
+    let mut rest = text.trim();
+    core::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let end = match rest.char_indices().nth(width) {
+            None => rest.len(),
+            // a space right after the line is as good as one inside it
+            Some((idx, ' ')) => idx,
+            Some((idx, _)) => rest[..idx].rfind(' ').unwrap_or(idx),
+        };
+        let (line, next) = rest.split_at(end);
+        rest = next.trim_start();
+        Some(line.trim_end())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notices_expire_or_are_dismissed() {
+        let mut notices = Notices::default();
+        let now = Tick::default();
+        let notice = Notice {
+            title: "Maintenance".to_string(),
+            severity: Severity::Warning.into(),
+            expires_secs: Some(60),
+            ..Default::default()
+        };
+        notices.show(&notice, now).unwrap();
+        assert_eq!(notices.active().unwrap().severity, Severity::Warning);
+        assert!(!notices.update(now + Duration::from_secs(59)));
+        assert!(notices.update(now + Duration::from_secs(60)));
+        assert!(notices.active().is_none());
+
+        // without an expiry it stays until a press
+        notices.show(&from_text("Dinner is ready"), now).unwrap();
+        assert!(!notices.update(now + Duration::from_secs(24 * 60 * 60)));
+        assert!(notices.dismiss());
+        assert!(!notices.dismiss());
+        assert_eq!(notices.show(&from_text("  "), now), Err(NoticeError::Empty));
+    }
+
+    #[test]
+    fn text_is_wrapped_at_spaces() {
+        let lines: Vec<_> = wrap("the inverter is serviced on monday", 14).collect();
+        assert_eq!(lines, ["the inverter", "is serviced on", "monday"]);
+        let lines: Vec<_> = wrap("abcdefghij klm", 4).collect();
+        assert_eq!(lines, ["abcd", "efgh", "ij", "klm"]);
+        assert_eq!(wrap("  ", 4).count(), 0);
+    }
+
+    #[test]
+    fn banner_text_is_cut_to_a_line() {
+        assert_eq!(line("Maintenance", "monday"), "Maintenance: monday");
+        assert_eq!(line("", "Dinner is ready"), "Dinner is ready");
+        assert_eq!(line("Maintenance", ""), "Maintenance");
+        let long = "the inverter is serviced on monday, the battery stays empty";
+        assert_eq!(line("", long).chars().count(), LINE_CHARS);
+        // wider characters stop at the end of the buffer
+        assert_eq!(line("Wärmepumpe", &"ö".repeat(60)).len(), 63);
+    }
+}
@@ -0,0 +1,754 @@
+// the screens of the display. the page manager owns the pages and only draws the active one,
+// the data for all of them is kept in the dashboard state
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::borrow::BorrowMut;
+use core::fmt::Debug;
+use core::time::Duration;
+
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::prelude::OriginDimensions;
+
+use crate::clock::Tick;
+use crate::color::DisplayColor;
+use crate::dashboard::{Changes, DashboardState};
+use crate::error::Result;
+use crate::night::NightSummary;
+use crate::widgets::{self, DashboardWidget, Widget};
+use crate::DisplayBoxed;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+    Next,
+    Previous,
+    // what it does is up to the page
+    Select,
+}
+
+pub trait Page<T: DrawTarget> {
+    fn title(&self) -> &'static str;
+    // draws the whole screen
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()>;
+    // redraws what changed since the last update
+    fn update(
+        &mut self,
+        display: &mut DisplayBoxed<T>,
+        state: &DashboardState,
+        changes: Changes,
+    ) -> Result<()>;
+    fn select(&mut self, _display: &mut DisplayBoxed<T>, _state: &DashboardState) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub struct PageManager<T: DrawTarget> {
+    pages: Vec<Box<dyn Page<T>>>,
+    active: usize,
+    // the pages are cycled through after this long without input
+    rotation: Option<Duration>,
+    last_change: Option<Tick>,
+    // the page of the pictures of the server, added after the others with the first one
+    screen: Option<usize>,
+}
+
+impl<T> PageManager<T>
+where
+    T: DrawTarget + OriginDimensions + 'static,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    pub fn new(first: Box<dyn Page<T>>) -> Self {
+        PageManager {
+            pages: vec![first],
+            active: 0,
+            rotation: None,
+            last_change: None,
+            screen: None,
+        }
+    }
+    pub fn with_page(mut self, page: Box<dyn Page<T>>) -> Self {
+        self.pages.push(page);
+        self
+    }
+    // the page is drawn the next time it becomes active
+    pub fn set_page(&mut self, index: usize, page: Box<dyn Page<T>>) {
+        self.pages[index] = page;
+    }
+    pub fn with_rotation(mut self, rotation: Duration) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+    pub fn active(&self) -> usize {
+        self.active
+    }
+    pub fn title(&self) -> &'static str {
+        self.pages[self.active].title()
+    }
+    pub fn len(&self) -> usize {
+        self.pages.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+    pub fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        // the summary stands in for all pages at night
+        if state.night.is_active() {
+            let summary = NightSummary::new(&state.ui, &state.night, state.now);
+            display.draw_night_summary(&summary)?;
+        } else {
+            self.pages[self.active].draw(display, state)?;
+        }
+        draw_overlays(display, state)
+    }
+    // the other pages draw the current state once they become active
+    pub fn update(
+        &mut self,
+        display: &mut DisplayBoxed<T>,
+        state: &DashboardState,
+        changes: Changes,
+    ) -> Result<()> {
+        // a layout from the server replaces the dashboard, the first page
+        if changes.layout {
+            if let Some(layout) = &state.layout {
+                self.set_page(0, Box::new(layout.page()));
+            }
+        }
+        // a picture from the server is shown right away on a page of its own, so the dashboard
+        // is still there with the buttons or the rotation. the rotation waits as after an input
+        if changes.bitmap && state.screen.is_some() {
+            let pages = &mut self.pages;
+            self.active = *self.screen.get_or_insert_with(|| {
+                pages.push(Box::new(ScreenPage));
+                pages.len() - 1
+            });
+            self.last_change = Some(state.now);
+        }
+        // a cleared banner or card leaves a hole, so the page starts over. the night summary is
+        // small enough to be drawn from scratch every time
+        let replaced = changes.layout || changes.bitmap;
+        let overlays = changes.alert || changes.notice;
+        if overlays || changes.night || replaced || state.night.is_active() {
+            return self.draw(display, state);
+        }
+        self.pages[self.active].update(display, state, changes)?;
+        draw_overlays(display, state)
+    }
+    pub fn handle(
+        &mut self,
+        event: InputEvent,
+        display: &mut DisplayBoxed<T>,
+        state: &DashboardState,
+        now: Tick,
+    ) -> Result<()> {
+        // any input pauses the rotation
+        self.last_change = Some(now);
+        match event {
+            InputEvent::Next => self.show((self.active + 1) % self.pages.len(), display, state),
+            InputEvent::Previous => self.show(
+                (self.active + self.pages.len() - 1) % self.pages.len(),
+                display,
+                state,
+            ),
+            InputEvent::Select => {
+                self.pages[self.active].select(display, state)?;
+                draw_overlays(display, state)
+            }
+        }
+    }
+    // returns true if the next page was shown
+    pub fn tick(
+        &mut self,
+        now: Tick,
+        display: &mut DisplayBoxed<T>,
+        state: &DashboardState,
+    ) -> Result<bool> {
+        let (Some(rotation), Some(last)) = (self.rotation, self.last_change) else {
+            self.last_change.get_or_insert(now);
+            return Ok(false);
+        };
+        if self.pages.len() < 2 || state.night.is_active() || now.duration_since(last) < rotation {
+            return Ok(false);
+        }
+        self.last_change = Some(now);
+        self.show((self.active + 1) % self.pages.len(), display, state)?;
+        Ok(true)
+    }
+    fn show(
+        &mut self,
+        page: usize,
+        display: &mut DisplayBoxed<T>,
+        state: &DashboardState,
+    ) -> Result<()> {
+        if page == self.active {
+            return Ok(());
+        }
+        self.active = page;
+        self.draw(display, state)
+    }
+}
+
+// the banner of the most important alert goes on top of every page, the pages can draw over it.
+// a notice goes on top of that, the banner at the bottom or the card over everything
+fn draw_overlays<T>(display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()>
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    if let Some(alert) = state.alerts.active() {
+        display.draw_alert_banner(&alert)?;
+    }
+    match state.notices.active() {
+        Some(notice) if notice.is_card() => display.draw_notice_card(notice),
+        Some(notice) => display.draw_notice_banner(notice),
+        None => Ok(()),
+    }
+}
+
+// the power flow, weather, totals and chart
+#[derive(Debug, Default)]
+pub struct DashboardPage {
+    dashboard: DashboardWidget,
+}
+
+impl DashboardPage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T> Page<T> for DashboardPage
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn title(&self) -> &'static str {
+        "Dashboard"
+    }
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        display.clear(T::Color::WHITE)?;
+        self.dashboard.draw(display, state)
+    }
+    fn update(
+        &mut self,
+        display: &mut DisplayBoxed<T>,
+        state: &DashboardState,
+        changes: Changes,
+    ) -> Result<()> {
+        widgets::update_all([&mut self.dashboard], display, state, changes)
+    }
+    // toggles the gridlines of the chart
+    fn select(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        Widget::<T>::select(&mut self.dashboard);
+        widgets::update_all([&mut self.dashboard], display, state, Changes::default())
+    }
+}
+
+// a dashboard put together from widgets, each drawn where it was moved to
+pub struct WidgetPage<T: DrawTarget> {
+    title: &'static str,
+    widgets: Vec<Box<dyn Widget<T>>>,
+}
+
+impl<T: DrawTarget> WidgetPage<T> {
+    pub fn new(title: &'static str) -> Self {
+        WidgetPage {
+            title,
+            widgets: Vec::new(),
+        }
+    }
+    pub fn with_widget(mut self, widget: Box<dyn Widget<T>>) -> Self {
+        self.widgets.push(widget);
+        self
+    }
+    pub fn widgets(&self) -> &[Box<dyn Widget<T>>] {
+        &self.widgets
+    }
+}
+
+impl<T> Page<T> for WidgetPage<T>
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn title(&self) -> &'static str {
+        self.title
+    }
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        display.clear(T::Color::WHITE)?;
+        widgets::draw_all(self.widgets.iter_mut().map(|w| w.as_mut()), display, state)
+    }
+    fn update(
+        &mut self,
+        display: &mut DisplayBoxed<T>,
+        state: &DashboardState,
+        changes: Changes,
+    ) -> Result<()> {
+        widgets::update_all(
+            self.widgets.iter_mut().map(|w| w.as_mut()),
+            display,
+            state,
+            changes,
+        )
+    }
+    fn select(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        self.widgets.iter_mut().for_each(|w| w.select());
+        self.update(display, state, Changes::default())
+    }
+}
+
+// grid power, voltage and inverter power per phase
+#[derive(Debug, Default)]
+pub struct PhasePage;
+
+impl<T> Page<T> for PhasePage
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn title(&self) -> &'static str {
+        "Phases"
+    }
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        draw_phases(display, state)
+    }
+    fn update(
+        &mut self,
+        display: &mut DisplayBoxed<T>,
+        state: &DashboardState,
+        changes: Changes,
+    ) -> Result<()> {
+        if changes.phases {
+            self.draw(display, state)?;
+        }
+        Ok(())
+    }
+}
+
+// the phase page and its widget
+pub(crate) fn draw_phases<T, B>(
+    display: &mut DisplayBoxed<T, B>,
+    state: &DashboardState,
+) -> Result<()>
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+    B: BorrowMut<T>,
+{
+    match &state.phases {
+        Some(Ok(summary)) => display.draw_phase_page(summary),
+        Some(Err(e)) => {
+            display.clear(T::Color::WHITE)?;
+            display.draw_status_message(e.label())
+        }
+        None => {
+            display.clear(T::Color::WHITE)?;
+            display.draw_status_message("No phase data")
+        }
+    }
+}
+
+// state of charge, details and the time until full or empty
+#[derive(Debug, Default)]
+pub struct BatteryPage;
+
+impl<T> Page<T> for BatteryPage
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn title(&self) -> &'static str {
+        "Battery"
+    }
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        display.draw_battery_page(&state.battery)
+    }
+    fn update(
+        &mut self,
+        display: &mut DisplayBoxed<T>,
+        state: &DashboardState,
+        changes: Changes,
+    ) -> Result<()> {
+        if changes.battery {
+            self.draw(display, state)?;
+        }
+        Ok(())
+    }
+}
+
+// diagnostics of the device, redrawn with every message so the ages stay current
+#[derive(Debug, Default)]
+pub struct SystemPage;
+
+impl<T> Page<T> for SystemPage
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn title(&self) -> &'static str {
+        "System"
+    }
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        display.draw_system_page(&state.system, state.now)
+    }
+    fn update(
+        &mut self,
+        display: &mut DisplayBoxed<T>,
+        state: &DashboardState,
+        _changes: Changes,
+    ) -> Result<()> {
+        self.draw(display, state)
+    }
+}
+
+// the pictures the server sent, for content the firmware knows nothing about
+pub struct ScreenPage;
+
+impl<T> Page<T> for ScreenPage
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn title(&self) -> &'static str {
+        "Screen"
+    }
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        match &state.screen {
+            Some(screen) => display.draw_screen(screen),
+            None => {
+                display.clear(T::Color::WHITE)?;
+                Ok(())
+            }
+        }
+    }
+    fn update(
+        &mut self,
+        display: &mut DisplayBoxed<T>,
+        state: &DashboardState,
+        changes: Changes,
+    ) -> Result<()> {
+        if changes.bitmap {
+            self.draw(display, state)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DisplayError;
+    use crate::night::{NightConfig, Schedule};
+    use embedded_graphics::prelude::{Point, Size};
+    use embedded_graphics::Pixel;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    struct NullTarget;
+
+    impl DrawTarget for NullTarget {
+        type Color = epd_waveshare::color::Color;
+        type Error = std::convert::Infallible;
+        fn draw_iter<I>(&mut self, _pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            Ok(())
+        }
+    }
+
+    impl OriginDimensions for NullTarget {
+        fn size(&self) -> Size {
+            Size::new(296, 128)
+        }
+    }
+
+    // counts how often the page was drawn
+    type Counter = Rc<Cell<u32>>;
+
+    struct CountingPage {
+        title: &'static str,
+        draws: Counter,
+        selects: Counter,
+    }
+
+    impl CountingPage {
+        fn boxed(title: &'static str) -> (Box<dyn Page<NullTarget>>, Counter, Counter) {
+            let draws = Rc::new(Cell::new(0));
+            let selects = Rc::new(Cell::new(0));
+            let page = CountingPage {
+                title,
+                draws: draws.clone(),
+                selects: selects.clone(),
+            };
+            (Box::new(page), draws, selects)
+        }
+    }
+
+    impl Page<NullTarget> for CountingPage {
+        fn title(&self) -> &'static str {
+            self.title
+        }
+        fn draw(
+            &mut self,
+            _display: &mut DisplayBoxed<NullTarget>,
+            _state: &DashboardState,
+        ) -> Result<()> {
+            self.draws.set(self.draws.get() + 1);
+            Ok(())
+        }
+        fn update(
+            &mut self,
+            display: &mut DisplayBoxed<NullTarget>,
+            state: &DashboardState,
+            _changes: Changes,
+        ) -> Result<()> {
+            self.draw(display, state)
+        }
+        fn select(
+            &mut self,
+            _display: &mut DisplayBoxed<NullTarget>,
+            _state: &DashboardState,
+        ) -> Result<()> {
+            self.selects.set(self.selects.get() + 1);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn navigation_wraps_around() {
+        let mut display = DisplayBoxed::new(NullTarget);
+        let state = DashboardState::default();
+        let now = Tick::default();
+        let (first, _, _) = CountingPage::boxed("first");
+        let (second, second_draws, _) = CountingPage::boxed("second");
+        let (third, _, third_selects) = CountingPage::boxed("third");
+        let mut pages = PageManager::new(first).with_page(second).with_page(third);
+
+        pages
+            .handle(InputEvent::Next, &mut display, &state, now)
+            .unwrap();
+        assert_eq!(pages.title(), "second");
+        assert_eq!(second_draws.get(), 1);
+        pages
+            .handle(InputEvent::Next, &mut display, &state, now)
+            .unwrap();
+        pages
+            .handle(InputEvent::Select, &mut display, &state, now)
+            .unwrap();
+        assert_eq!(third_selects.get(), 1);
+        pages
+            .handle(InputEvent::Next, &mut display, &state, now)
+            .unwrap();
+        assert_eq!(pages.active(), 0);
+        pages
+            .handle(InputEvent::Previous, &mut display, &state, now)
+            .unwrap();
+        assert_eq!(pages.title(), "third");
+    }
+
+    #[test]
+    fn only_the_active_page_is_updated() {
+        let mut display = DisplayBoxed::new(NullTarget);
+        let state = DashboardState::default();
+        let (first, first_draws, _) = CountingPage::boxed("first");
+        let (second, second_draws, _) = CountingPage::boxed("second");
+        let mut pages = PageManager::new(first).with_page(second);
+        pages.update(&mut display, &state, Changes::all()).unwrap();
+        assert_eq!(first_draws.get(), 1);
+        assert_eq!(second_draws.get(), 0);
+    }
+
+    #[test]
+    fn pages_rotate_without_input() {
+        let mut display = DisplayBoxed::new(NullTarget);
+        let state = DashboardState::default();
+        let start = Tick::default();
+        let (first, _, _) = CountingPage::boxed("first");
+        let (second, _, _) = CountingPage::boxed("second");
+        let mut pages = PageManager::new(first)
+            .with_page(second)
+            .with_rotation(Duration::from_secs(30));
+        assert!(!pages.tick(start, &mut display, &state).unwrap());
+        assert!(!pages
+            .tick(start + Duration::from_secs(20), &mut display, &state)
+            .unwrap());
+        assert!(pages
+            .tick(start + Duration::from_secs(30), &mut display, &state)
+            .unwrap());
+        assert_eq!(pages.active(), 1);
+        // input restarts the timer
+        pages
+            .handle(
+                InputEvent::Select,
+                &mut display,
+                &state,
+                start + Duration::from_secs(50),
+            )
+            .unwrap();
+        assert!(!pages
+            .tick(start + Duration::from_secs(70), &mut display, &state)
+            .unwrap());
+        assert!(pages
+            .tick(start + Duration::from_secs(80), &mut display, &state)
+            .unwrap());
+        assert_eq!(pages.active(), 0);
+    }
+
+    #[test]
+    fn the_night_summary_replaces_the_pages() {
+        let mut display = DisplayBoxed::new(NullTarget);
+        let mut state = DashboardState::default();
+        state.night.set_config(NightConfig {
+            schedule: Schedule::parse("22:00-06:00").unwrap(),
+            ..Default::default()
+        });
+        let now = Tick::default();
+        state.night.set_clock(23 * 60, now);
+        let (first, first_draws, _) = CountingPage::boxed("first");
+        let (second, _, _) = CountingPage::boxed("second");
+        let mut pages = PageManager::new(first)
+            .with_page(second)
+            .with_rotation(Duration::from_secs(1));
+        let changes = state.tick(now);
+        assert!(changes.night);
+        pages.update(&mut display, &state, changes).unwrap();
+        assert_eq!(first_draws.get(), 0);
+        // no rotation at night
+        pages.tick(now, &mut display, &state).unwrap();
+        assert!(!pages
+            .tick(now + Duration::from_secs(5), &mut display, &state)
+            .unwrap());
+        // a press brings the pages back
+        let changes = state.wake(now);
+        pages.update(&mut display, &state, changes).unwrap();
+        assert_eq!(first_draws.get(), 1);
+    }
+
+    #[test]
+    fn real_pages_draw_without_data() {
+        let mut display = DisplayBoxed::new(NullTarget);
+        let state = DashboardState::default();
+        let mut pages = PageManager::new(Box::new(DashboardPage::new()))
+            .with_page(Box::new(PhasePage))
+            .with_page(Box::new(BatteryPage))
+            .with_page(Box::new(SystemPage));
+        for _ in 0..pages.len() {
+            pages.draw(&mut display, &state).unwrap();
+            pages.update(&mut display, &state, Changes::all()).unwrap();
+            pages
+                .handle(InputEvent::Next, &mut display, &state, Tick::default())
+                .unwrap();
+        }
+        assert_eq!(pages.active(), 0);
+    }
+
+    #[test]
+    fn a_bitmap_gets_a_page_of_its_own() {
+        use crate::prototypes::types::data::Oneof;
+        use crate::prototypes::types::{Bitmap, Data};
+
+        let mut display = DisplayBoxed::new(NullTarget);
+        let mut state = DashboardState::default();
+        let (first, _, _) = CountingPage::boxed("first");
+        let (second, _, _) = CountingPage::boxed("second");
+        let mut pages = PageManager::new(first).with_page(second);
+        pages
+            .handle(InputEvent::Next, &mut display, &state, Tick::default())
+            .unwrap();
+        let bitmap = Data {
+            oneof: Some(Oneof::Bitmap(Bitmap {
+                width: 8,
+                height: 1,
+                pixels: vec![0xff],
+                ..Default::default()
+            })),
+        };
+        let changes = state.apply(&bitmap, Tick::default());
+        pages.update(&mut display, &state, changes).unwrap();
+        assert_eq!(pages.title(), "Screen");
+        assert_eq!(pages.len(), 3);
+        assert!(state.screen.as_ref().unwrap().is_black(Point::new(7, 0)));
+
+        // the dashboard comes back with the next page
+        pages
+            .handle(InputEvent::Next, &mut display, &state, Tick::default())
+            .unwrap();
+        assert_eq!(pages.title(), "first");
+
+        // the next picture goes on the same page
+        let changes = state.apply(&bitmap, Tick::default());
+        pages.update(&mut display, &state, changes).unwrap();
+        assert_eq!(pages.title(), "Screen");
+        assert_eq!(pages.len(), 3);
+    }
+
+    // an oled whose bus gives up after a number of pixels
+    struct FlakyOled {
+        pixels_left: usize,
+    }
+
+    impl DrawTarget for FlakyOled {
+        type Color = embedded_graphics::pixelcolor::BinaryColor;
+        type Error = &'static str;
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            for _ in pixels {
+                self.pixels_left = self.pixels_left.checked_sub(1).ok_or("bus timeout")?;
+            }
+            Ok(())
+        }
+    }
+
+    impl OriginDimensions for FlakyOled {
+        fn size(&self) -> Size {
+            Size::new(128, 64)
+        }
+    }
+
+    #[test]
+    fn other_colours_and_display_errors() {
+        use embedded_graphics::mock_display::MockDisplay;
+        use embedded_graphics::pixelcolor::Rgb565;
+
+        let state = DashboardState::default();
+        let mut tft: MockDisplay<Rgb565> = MockDisplay::new();
+        tft.set_allow_out_of_bounds_drawing(true);
+        tft.set_allow_overdraw(true);
+        let mut display = DisplayBoxed::new(tft);
+        PageManager::new(Box::new(DashboardPage::new()))
+            .draw(&mut display, &state)
+            .unwrap();
+
+        let mut display = DisplayBoxed::new(FlakyOled { pixels_left: 100 });
+        let err = PageManager::new(Box::new(DashboardPage::new()))
+            .draw(&mut display, &state)
+            .unwrap_err();
+        assert_eq!(err, DisplayError::Draw(text!("\"bus timeout\"").unwrap()));
+        assert!(!err.is_recoverable());
+        assert_eq!(
+            err.to_string(),
+            "error drawing on the display: \"bus timeout\""
+        );
+
+        // drawn on directly, the error of the display itself is still there
+        let mut display = DisplayBoxed::new(FlakyOled { pixels_left: 0 });
+        let err = display
+            .clear(embedded_graphics::pixelcolor::BinaryColor::On)
+            .unwrap_err();
+        assert_eq!(err.0, "bus timeout");
+        assert!(
+            matches!(DisplayError::from(err), DisplayError::Draw(text) if text == "\"bus timeout\"")
+        );
+    }
+}
@@ -0,0 +1,397 @@
+// the waveshare panels the display can be built for. the pages are laid out for the 296x128 of
+// the 2.9" panel and drawn at the top in the middle of the larger ones. the pixels of all of
+// them are about the same size, so nothing is scaled. the larger panels have a layout of their
+// own for the dashboard, which puts the detail pages around it
+use core::convert::Infallible;
+
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::prelude::{OriginDimensions, Point, Size};
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::Pixel;
+use epd_waveshare::color::{Color, TriColor};
+use epd_waveshare::prelude::DisplayRotation;
+use epd_waveshare::{epd2in9, epd2in9_v2, epd2in9bc, epd4in2, epd7in5_v2};
+
+use crate::color::DisplayColor;
+
+// the size every page is drawn in
+pub const CANVAS_WIDTH: u32 = 296;
+pub const CANVAS_HEIGHT: u32 = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PanelSpec {
+    // as used for PANEL in the .env and --panel of the simulator
+    pub name: &'static str,
+    // in landscape, like the pages are drawn
+    pub width: u32,
+    pub height: u32,
+    // the quick refresh without flashing, the other panels always do a full one
+    pub partial_refresh: bool,
+    // red, black and white
+    pub tri_color: bool,
+    // the ones that give a landscape picture, the first is the default
+    pub rotations: &'static [Rotation],
+    // the layout of the dashboard, see layout::PageLayout. the 2.9" panels show the stock one
+    pub dashboard: Option<&'static str>,
+}
+
+// the rotations of epd-waveshare, which can not be compared or printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    Rotate0,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+impl From<Rotation> for DisplayRotation {
+    fn from(rotation: Rotation) -> Self {
+        match rotation {
+            Rotation::Rotate0 => DisplayRotation::Rotate0,
+            Rotation::Rotate90 => DisplayRotation::Rotate90,
+            Rotation::Rotate180 => DisplayRotation::Rotate180,
+            Rotation::Rotate270 => DisplayRotation::Rotate270,
+        }
+    }
+}
+
+impl PanelSpec {
+    pub fn by_name(name: &str) -> Option<PanelSpec> {
+        PANELS.iter().find(|spec| spec.name == name.trim()).copied()
+    }
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+    pub fn layout(&self) -> Layout {
+        Layout::fit(self.size())
+    }
+}
+
+pub trait Panel {
+    type Color: DisplayColor;
+    type Display: DrawTarget<Color = Self::Color, Error = Infallible> + OriginDimensions + Default;
+    const SPEC: PanelSpec;
+    // what is sent to the panel, the black and white plane on the tri colour panels
+    fn buffer(display: &Self::Display) -> &[u8];
+    // the red plane
+    fn chromatic_buffer(_display: &Self::Display) -> Option<&[u8]> {
+        None
+    }
+    fn set_rotation(display: &mut Self::Display, rotation: DisplayRotation);
+    // an empty display in the default rotation to draw the pages on
+    fn canvas() -> Canvas<Self::Display> {
+        let mut display = Self::Display::default();
+        Self::set_rotation(&mut display, Self::SPEC.rotations[0].into());
+        Canvas::new(display)
+    }
+}
+
+// the portrait panels are turned on their side
+const PORTRAIT: &[Rotation] = &[Rotation::Rotate90, Rotation::Rotate270];
+const LANDSCAPE: &[Rotation] = &[Rotation::Rotate0, Rotation::Rotate180];
+
+// 2.9" v1, epd-waveshare has no quick refresh for it
+pub struct Epd2in9;
+
+impl Panel for Epd2in9 {
+    type Color = Color;
+    type Display = epd2in9::Display2in9;
+    const SPEC: PanelSpec = PanelSpec {
+        name: "2in9",
+        width: 296,
+        height: 128,
+        partial_refresh: false,
+        tri_color: false,
+        rotations: PORTRAIT,
+        dashboard: None,
+    };
+    fn buffer(display: &Self::Display) -> &[u8] {
+        display.buffer()
+    }
+    fn set_rotation(display: &mut Self::Display, rotation: DisplayRotation) {
+        display.set_rotation(rotation);
+    }
+}
+
+// 2.9" v2, the panel the enclosure is made for
+pub struct Epd2in9V2;
+
+impl Panel for Epd2in9V2 {
+    type Color = Color;
+    type Display = epd2in9_v2::Display2in9;
+    const SPEC: PanelSpec = PanelSpec {
+        name: "2in9_v2",
+        width: 296,
+        height: 128,
+        partial_refresh: true,
+        tri_color: false,
+        rotations: PORTRAIT,
+        dashboard: None,
+    };
+    fn buffer(display: &Self::Display) -> &[u8] {
+        display.buffer()
+    }
+    fn set_rotation(display: &mut Self::Display, rotation: DisplayRotation) {
+        display.set_rotation(rotation);
+    }
+}
+
+// 2.9" b/c, red or yellow besides black and white
+pub struct Epd2in9bc;
+
+impl Panel for Epd2in9bc {
+    type Color = TriColor;
+    // the display of epd2in9bc only has the black and white plane, this one has both
+    type Display = epd_waveshare::graphics::Display<
+        { epd2in9bc::WIDTH },
+        { epd2in9bc::HEIGHT },
+        true,
+        { 2 * 4736 },
+        TriColor,
+    >;
+    const SPEC: PanelSpec = PanelSpec {
+        name: "2in9bc",
+        width: 296,
+        height: 128,
+        partial_refresh: false,
+        tri_color: true,
+        rotations: PORTRAIT,
+        dashboard: None,
+    };
+    fn buffer(display: &Self::Display) -> &[u8] {
+        display.bw_buffer()
+    }
+    fn chromatic_buffer(display: &Self::Display) -> Option<&[u8]> {
+        Some(display.chromatic_buffer())
+    }
+    fn set_rotation(display: &mut Self::Display, rotation: DisplayRotation) {
+        display.set_rotation(rotation);
+    }
+}
+
+// 4.2", the battery page goes below the dashboard
+pub struct Epd4in2;
+
+const LAYOUT_4IN2: &str = r#"
+[[widget]]
+type = "dashboard"
+region = [52, 0, 296, 128]
+
+[[widget]]
+type = "battery_page"
+region = [52, 150, 296, 128]
+"#;
+
+impl Panel for Epd4in2 {
+    type Color = Color;
+    type Display = epd4in2::Display4in2;
+    const SPEC: PanelSpec = PanelSpec {
+        name: "4in2",
+        width: 400,
+        height: 300,
+        partial_refresh: true,
+        tri_color: false,
+        rotations: LANDSCAPE,
+        dashboard: Some(LAYOUT_4IN2),
+    };
+    fn buffer(display: &Self::Display) -> &[u8] {
+        display.buffer()
+    }
+    fn set_rotation(display: &mut Self::Display, rotation: DisplayRotation) {
+        display.set_rotation(rotation);
+    }
+}
+
+// 7.5" v2, room for all the detail pages, the battery and the phases in the middle and the
+// system at the bottom
+pub struct Epd7in5;
+
+const LAYOUT_7IN5: &str = r#"
+[[widget]]
+type = "dashboard"
+region = [252, 0, 296, 128]
+
+[[widget]]
+type = "battery_page"
+region = [96, 164, 296, 128]
+
+[[widget]]
+type = "phase_page"
+region = [408, 164, 296, 128]
+
+[[widget]]
+type = "system_page"
+region = [252, 328, 296, 128]
+"#;
+
+impl Panel for Epd7in5 {
+    type Color = Color;
+    type Display = epd7in5_v2::Display7in5;
+    const SPEC: PanelSpec = PanelSpec {
+        name: "7in5",
+        width: 800,
+        height: 480,
+        partial_refresh: false,
+        tri_color: false,
+        rotations: LANDSCAPE,
+        dashboard: Some(LAYOUT_7IN5),
+    };
+    fn buffer(display: &Self::Display) -> &[u8] {
+        display.buffer()
+    }
+    fn set_rotation(display: &mut Self::Display, rotation: DisplayRotation) {
+        display.set_rotation(rotation);
+    }
+}
+
+pub const PANELS: [PanelSpec; 5] = [
+    Epd2in9::SPEC,
+    Epd2in9V2::SPEC,
+    Epd2in9bc::SPEC,
+    Epd4in2::SPEC,
+    Epd7in5::SPEC,
+];
+
+// where the pages end up on the panel, (0, 0) of the pages is at the offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub offset: Point,
+}
+
+impl Layout {
+    // at the top in the middle, so the banners of the alerts and notices stay at the top
+    pub fn fit(size: Size) -> Self {
+        Layout {
+            offset: Point::new((size.width.saturating_sub(CANVAS_WIDTH) / 2) as i32, 0),
+        }
+    }
+    // from the pages to the panel
+    pub fn map(&self, area: &Rectangle) -> Rectangle {
+        Rectangle::new(self.offset + area.top_left, area.size)
+    }
+}
+
+// the 296x128 the pages draw on, put onto the display of the panel. the widgets of a layout can
+// draw on the rest of the panel, what is drawn outside of the panel is dropped
+pub struct Canvas<T> {
+    target: T,
+    layout: Layout,
+}
+
+impl<T: OriginDimensions> Canvas<T> {
+    pub fn new(target: T) -> Self {
+        let layout = Layout::fit(target.size());
+        Canvas { target, layout }
+    }
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+    pub fn inner(&self) -> &T {
+        &self.target
+    }
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.target
+    }
+}
+
+impl<T> DrawTarget for Canvas<T>
+where
+    T: DrawTarget + OriginDimensions,
+{
+    type Color = T::Color;
+    type Error = T::Error;
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let panel = self.target.bounding_box();
+        let offset = self.layout.offset;
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(point, color)| Pixel(point + offset, color))
+                .filter(|Pixel(point, _)| panel.contains(*point)),
+        )
+    }
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = self
+            .layout
+            .map(area)
+            .intersection(&self.target.bounding_box());
+        self.target.fill_solid(&area, color)
+    }
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.target.clear(color)
+    }
+}
+
+impl<T> OriginDimensions for Canvas<T> {
+    fn size(&self) -> Size {
+        Size::new(CANVAS_WIDTH, CANVAS_HEIGHT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // remembers the black pixels
+    struct Pixels {
+        size: Size,
+        black: Vec<Point>,
+    }
+
+    impl DrawTarget for Pixels {
+        type Color = Color;
+        type Error = Infallible;
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            for Pixel(point, color) in pixels {
+                if color == Color::Black {
+                    self.black.push(point);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl OriginDimensions for Pixels {
+        fn size(&self) -> Size {
+            self.size
+        }
+    }
+
+    #[test]
+    fn layouts_of_the_panels() {
+        let layouts: Vec<_> = PANELS.iter().map(|spec| spec.layout()).collect();
+        assert_eq!(layouts[1].offset, Point::zero());
+        assert_eq!(layouts[3].offset, Point::new(52, 0));
+        assert_eq!(layouts[4].offset, Point::new(252, 0));
+        assert_eq!(PanelSpec::by_name("7in5"), Some(Epd7in5::SPEC));
+        assert_eq!(PanelSpec::by_name("1in54"), None);
+    }
+
+    #[test]
+    fn pixels_are_moved_and_clipped() {
+        let mut canvas = Canvas::new(Pixels {
+            size: Size::new(400, 300),
+            black: Vec::new(),
+        });
+        assert_eq!(canvas.size(), Size::new(296, 128));
+        canvas
+            .draw_iter([
+                Pixel(Point::new(1, 2), Color::Black),
+                // left of the pages, but still on the panel
+                Pixel(Point::new(-52, 200), Color::Black),
+                Pixel(Point::new(-53, 0), Color::Black),
+                Pixel(Point::new(0, 300), Color::Black),
+            ])
+            .unwrap();
+        assert_eq!(
+            canvas.inner().black,
+            [Point::new(53, 2), Point::new(0, 200)]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+// per phase breakdown of the grid meter and the inverter. a heat pump or charger that only
+// loads one phase shows up as imbalance between the phases
+use crate::prototypes::types::PhaseData;
+
+pub const PHASES: usize = 3;
+// below this the average load is too small for a meaningful percentage
+const MIN_AVERAGE_KW: f32 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhaseError {
+    MissingPhase,
+    InvalidValue,
+}
+
+impl PhaseError {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PhaseError::MissingPhase => "missing phase values",
+            PhaseError::InvalidValue => "bad phase value",
+        }
+    }
+}
+
+impl core::fmt::Display for PhaseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl core::error::Error for PhaseError {}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Phase {
+    // kW, positive when importing
+    pub grid_power: f32,
+    pub voltage: f32,
+    pub inverter_power: f32,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PhaseSummary {
+    pub phases: [Phase; PHASES],
+    pub frequency: f32,
+}
+
+impl PhaseSummary {
+    pub fn from_data(data: &PhaseData) -> Result<Self, PhaseError> {
+        if data.grid_power.len() < PHASES || data.grid_voltage.len() < PHASES {
+            return Err(PhaseError::MissingPhase);
+        }
+        let mut summary = PhaseSummary {
+            frequency: data.grid_frequency,
+            ..Default::default()
+        };
+        for (idx, phase) in summary.phases.iter_mut().enumerate() {
+            *phase = Phase {
+                grid_power: data.grid_power[idx],
+                voltage: data.grid_voltage[idx],
+                // older inverters only report the total
+                inverter_power: data.inverter_power.get(idx).copied().unwrap_or(0.0),
+            };
+        }
+        let finite = |phase: &Phase| {
+            phase.grid_power.is_finite()
+                && phase.voltage.is_finite()
+                && phase.inverter_power.is_finite()
+        };
+        if !summary.frequency.is_finite() || !summary.phases.iter().all(finite) {
+            return Err(PhaseError::InvalidValue);
+        }
+        Ok(summary)
+    }
+    // difference between the most and the least loaded phase in kW
+    pub fn imbalance_kw(&self) -> f32 {
+        let loads = self.loads();
+        let max = loads.iter().fold(f32::MIN, |acc, v| acc.max(*v));
+        let min = loads.iter().fold(f32::MAX, |acc, v| acc.min(*v));
+        max - min
+    }
+    // largest deviation from the average load, in percent of the average
+    pub fn imbalance_percent(&self) -> Option<f32> {
+        let loads = self.loads();
+        let average = loads.iter().sum::<f32>() / PHASES as f32;
+        if average < MIN_AVERAGE_KW {
+            return None;
+        }
+        let deviation = loads
+            .iter()
+            .fold(0.0f32, |acc, v| acc.max((v - average).abs()));
+        Some(deviation / average * 100.0)
+    }
+    pub fn most_loaded(&self) -> usize {
+        let loads = self.loads();
+        (0..PHASES)
+            .max_by(|a, b| loads[*a].total_cmp(&loads[*b]))
+            .unwrap_or(0)
+    }
+    // the load of a phase is what flows over the grid connection, in either direction
+    fn loads(&self) -> [f32; PHASES] {
+        self.phases.map(|phase| phase.grid_power.abs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(grid_power: [f32; 3]) -> PhaseData {
+        PhaseData {
+            grid_power: grid_power.to_vec(),
+            grid_voltage: vec![230.0, 231.0, 229.5],
+            inverter_power: vec![],
+            grid_frequency: 50.01,
+        }
+    }
+
+    #[test]
+    fn imbalance_of_a_single_phase_load() {
+        let summary = PhaseSummary::from_data(&data([3.0, 0.3, 0.3])).unwrap();
+        assert_eq!(summary.most_loaded(), 0);
+        assert!((summary.imbalance_kw() - 2.7).abs() < 1e-4);
+        assert!((summary.imbalance_percent().unwrap() - 150.0).abs() < 1e-3);
+        assert_eq!(summary.phases[1].inverter_power, 0.0);
+    }
+
+    #[test]
+    fn balanced_and_idle_phases() {
+        let summary = PhaseSummary::from_data(&data([1.0, -1.0, 1.0])).unwrap();
+        assert_eq!(summary.imbalance_kw(), 0.0);
+        assert_eq!(summary.imbalance_percent(), Some(0.0));
+        let summary = PhaseSummary::from_data(&data([0.0, 0.01, 0.0])).unwrap();
+        assert_eq!(summary.imbalance_percent(), None);
+    }
+
+    #[test]
+    fn invalid_data_is_rejected() {
+        let mut missing = data([1.0, 1.0, 1.0]);
+        missing.grid_voltage.pop();
+        assert_eq!(
+            PhaseSummary::from_data(&missing),
+            Err(PhaseError::MissingPhase)
+        );
+        assert_eq!(
+            PhaseSummary::from_data(&data([1.0, f32::NAN, 1.0])),
+            Err(PhaseError::InvalidValue)
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use crate::prototypes::types::Prediction;
+
+// the chart has one column per 10 minutes of the day
+pub const CHART_COLUMNS: usize = 144;
+pub const COLUMN_MINUTES: u16 = 10;
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+// defaults for older servers that only send the values
+const DEFAULT_INTERVAL_MINUTES: u32 = 5;
+const DEFAULT_SCALE: f32 = 1000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PredictionError {
+    Empty,
+    InvalidInterval,
+    InvalidStart,
+    InvalidScale,
+    InvalidValue,
+}
+
+impl PredictionError {
+    // short enough to fit into the chart area
+    pub fn label(&self) -> &'static str {
+        match self {
+            PredictionError::Empty => "no prediction values",
+            PredictionError::InvalidInterval => "bad prediction interval",
+            PredictionError::InvalidStart => "bad prediction start",
+            PredictionError::InvalidScale => "bad prediction scale",
+            PredictionError::InvalidValue => "bad prediction value",
+        }
+    }
+}
+
+impl core::fmt::Display for PredictionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl core::error::Error for PredictionError {}
+
+// resamples a prediction of any resolution to one value in kW per chart column. the values are
+// interpolated between the centers of the intervals, columns outside of the predicted range are 0
+pub fn resample(prediction: &Prediction) -> Result<[f32; CHART_COLUMNS], PredictionError> {
+    let interval = prediction
+        .interval_minutes
+        .unwrap_or(DEFAULT_INTERVAL_MINUTES);
+    if interval == 0 || interval > MINUTES_PER_DAY {
+        return Err(PredictionError::InvalidInterval);
+    }
+    let start = prediction.start_minute.unwrap_or(0);
+    if start >= MINUTES_PER_DAY {
+        return Err(PredictionError::InvalidStart);
+    }
+    let scale = prediction.scale.unwrap_or(DEFAULT_SCALE);
+    if !scale.is_finite() || scale <= 0.0 {
+        return Err(PredictionError::InvalidScale);
+    }
+    if prediction.prediction.is_empty() {
+        return Err(PredictionError::Empty);
+    }
+    if prediction.prediction.iter().any(|v| *v < 0) {
+        return Err(PredictionError::InvalidValue);
+    }
+
+    let values = &prediction.prediction;
+    let interval = interval as f32;
+    let start = start as f32;
+    let end = start + values.len() as f32 * interval;
+
+    let mut columns = [0.0; CHART_COLUMNS];
+    for (column, out) in columns.iter_mut().enumerate() {
+        let minute = (column as f32 + 0.5) * COLUMN_MINUTES as f32;
+        if minute < start || minute >= end {
+            continue;
+        }
+        // position relative to the centers of the intervals
+        let position = (minute - start) / interval - 0.5;
+        // floor and ceil, which core does not have
+        let lower = position.max(0.0) as usize;
+        let upper = if position > lower as f32 {
+            lower + 1
+        } else {
+            lower
+        }
+        .min(values.len() - 1);
+        let fraction = (position - lower as f32).clamp(0.0, 1.0);
+        let lower_value = values[lower.min(values.len() - 1)] as f32;
+        let upper_value = values[upper] as f32;
+        *out = (lower_value + (upper_value - lower_value) * fraction) / scale;
+    }
+    Ok(columns)
+}
+
+// expected energy in kWh from the start of the given column until the end of the day
+pub fn energy_kwh(columns: &[f32], from_column: usize) -> f32 {
+    columns.iter().skip(from_column).sum::<f32>() * COLUMN_MINUTES as f32 / 60.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn prediction(interval: u32, start: u32, values: Vec<i32>) -> Prediction {
+        Prediction {
+            prediction: values,
+            interval_minutes: Some(interval),
+            start_minute: Some(start),
+            scale: Some(1000.0),
+        }
+    }
+
+    fn assert_close(columns: &[f32], expected: &[(usize, f32)]) {
+        for (column, kw) in expected {
+            assert!(
+                (columns[*column] - kw).abs() < 1e-4,
+                "column {}: {} instead of {}",
+                column,
+                columns[*column],
+                kw
+            );
+        }
+    }
+
+    #[test]
+    fn five_minutes_are_averaged_per_column() {
+        let columns = resample(&prediction(5, 0, (0..288).map(|i| i * 10).collect())).unwrap();
+        assert_close(
+            &columns,
+            &[(0, 0.005), (1, 0.025), (100, 2.005), (143, 2.865)],
+        );
+    }
+
+    #[test]
+    fn quarter_hours_are_interpolated() {
+        let columns = resample(&prediction(15, 0, (0..96).map(|i| i * 150).collect())).unwrap();
+        // before the center of the first interval the first value is kept
+        assert_close(&columns, &[(0, 0.0), (1, 0.075), (2, 0.175), (60, 5.975)]);
+        // and so is the last one after the center of the last interval
+        assert_close(&columns, &[(143, 14.25)]);
+    }
+
+    #[test]
+    fn hours_from_a_start_minute() {
+        let columns =
+            resample(&prediction(60, 6 * 60, (0..15).map(|i| i * 600).collect())).unwrap();
+        assert_close(&columns, &[(35, 0.0), (36, 0.0), (39, 0.05), (42, 0.35)]);
+        assert_close(&columns, &[(125, 8.4), (126, 0.0), (143, 0.0)]);
+        // without the fields it is the 5 minutes of the older servers
+        let old = Prediction {
+            prediction: vec![1000; 288],
+            ..Default::default()
+        };
+        assert_close(&resample(&old).unwrap(), &[(0, 1.0), (143, 1.0)]);
+    }
+
+    #[test]
+    fn values_keep_the_order_they_were_sent_in() {
+        let columns = resample(&prediction(60, 0, vec![3000, 1000, 2000])).unwrap();
+        assert_close(
+            &columns,
+            &[
+                (0, 3.0),
+                (6, 3.0 - 2.0 * 7.0 / 12.0),
+                (12, 1.0 + 7.0 / 12.0),
+                (17, 2.0),
+            ],
+        );
+        assert_close(&columns, &[(18, 0.0)]);
+    }
+
+    #[test]
+    fn rejects_invalid_predictions() {
+        let resample = |interval, start, values: &[i32]| {
+            resample(&prediction(interval, start, values.to_vec()))
+        };
+        assert_eq!(resample(0, 0, &[1]), Err(PredictionError::InvalidInterval));
+        assert_eq!(
+            resample(24 * 60 + 1, 0, &[1]),
+            Err(PredictionError::InvalidInterval)
+        );
+        assert_eq!(
+            resample(60, 24 * 60, &[1]),
+            Err(PredictionError::InvalidStart)
+        );
+        assert_eq!(resample(60, 0, &[]), Err(PredictionError::Empty));
+        assert_eq!(
+            resample(60, 0, &[100, -1]),
+            Err(PredictionError::InvalidValue)
+        );
+        for scale in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            let invalid = Prediction {
+                scale: Some(scale),
+                ..prediction(60, 0, vec![1])
+            };
+            assert_eq!(
+                super::resample(&invalid),
+                Err(PredictionError::InvalidScale)
+            );
+        }
+    }
+}
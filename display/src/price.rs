@@ -0,0 +1,195 @@
+// hourly spot prices for households on a dynamic tariff. the forecast starts at midnight of
+// today and includes tomorrow once the prices are published, prices are in ct/kWh
+use crate::prototypes::types::PriceForecast;
+
+pub const MAX_PRICE_HOURS: usize = 48;
+// long enough for a washing machine or dishwasher
+pub const DEFAULT_WINDOW_HOURS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceError {
+    Empty,
+    TooManyHours,
+    InvalidValue,
+}
+
+impl PriceError {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PriceError::Empty => "no prices",
+            PriceError::TooManyHours => "too many price hours",
+            PriceError::InvalidValue => "bad price value",
+        }
+    }
+}
+
+impl core::fmt::Display for PriceError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl core::error::Error for PriceError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CheapestWindow {
+    pub start_hour: usize,
+    pub hours: usize,
+    pub average: f32,
+}
+
+// the contiguous window with the lowest average price that does not start before from_hour
+pub fn cheapest_window(prices: &[f32], from_hour: usize, hours: usize) -> Option<CheapestWindow> {
+    if hours == 0 || from_hour + hours > prices.len() {
+        return None;
+    }
+    (from_hour..=prices.len() - hours)
+        .map(|start| CheapestWindow {
+            start_hour: start,
+            hours,
+            average: prices[start..start + hours].iter().sum::<f32>() / hours as f32,
+        })
+        // the earliest window wins on equal prices
+        .fold(None, |best: Option<CheapestWindow>, window| match best {
+            Some(best) if best.average <= window.average => Some(best),
+            _ => Some(window),
+        })
+}
+
+// keeps the last forecast until the next one arrives
+#[derive(Debug, Default)]
+pub struct PriceCache {
+    prices: heapless::Vec<f32, MAX_PRICE_HOURS>,
+    last_minute: Option<u16>,
+}
+
+impl PriceCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn update(&mut self, forecast: &PriceForecast) -> Result<(), PriceError> {
+        if forecast.prices.is_empty() {
+            return Err(PriceError::Empty);
+        }
+        if forecast.prices.iter().any(|price| !price.is_finite()) {
+            return Err(PriceError::InvalidValue);
+        }
+        self.prices =
+            heapless::Vec::from_slice(&forecast.prices).map_err(|_| PriceError::TooManyHours)?;
+        // the forecast starts at midnight of the day it was sent, the minute of yesterday would
+        // shift it by a day once the clock moves on
+        self.last_minute = Some(0);
+        Ok(())
+    }
+    // returns true once a new hour started, so the strip needs to be redrawn
+    pub fn tick(&mut self, minute_of_day: u16) -> bool {
+        let new_hour = match self.last_minute {
+            Some(last) => {
+                // the clock jumped back by more than an hour, so a new day has started and
+                // tomorrow becomes today
+                if minute_of_day + 60 < last {
+                    let tomorrow = self.prices.get(24..).unwrap_or(&[]);
+                    self.prices = heapless::Vec::from_slice(tomorrow).unwrap_or_default();
+                }
+                minute_of_day / 60 != last / 60
+            }
+            None => true,
+        };
+        self.last_minute = Some(minute_of_day);
+        new_hour
+    }
+    pub fn prices(&self) -> &[f32] {
+        &self.prices
+    }
+    pub fn is_empty(&self) -> bool {
+        self.prices.is_empty()
+    }
+    pub fn current_hour(&self) -> Option<usize> {
+        self.last_minute.map(|minute| minute as usize / 60)
+    }
+    pub fn current_price(&self) -> Option<f32> {
+        self.prices.get(self.current_hour()?).copied()
+    }
+    // the window is searched from the current hour on, the past is of no use
+    pub fn cheapest_window(&self, hours: usize) -> Option<CheapestWindow> {
+        cheapest_window(&self.prices, self.current_hour().unwrap_or(0), hours)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forecast(prices: &[f32]) -> PriceForecast {
+        PriceForecast {
+            prices: prices.to_vec(),
+        }
+    }
+
+    #[test]
+    fn finds_the_cheapest_window() {
+        let prices = [30.0, 20.0, 10.0, 12.0, 25.0, 5.0, 40.0];
+        assert_eq!(
+            cheapest_window(&prices, 0, 2),
+            Some(CheapestWindow {
+                start_hour: 2,
+                hours: 2,
+                average: 11.0,
+            })
+        );
+        assert_eq!(cheapest_window(&prices, 0, 1).unwrap().start_hour, 5);
+        // windows starting in the past are skipped
+        assert_eq!(cheapest_window(&prices, 3, 2).unwrap().start_hour, 4);
+        assert_eq!(cheapest_window(&prices, 6, 2), None);
+        assert_eq!(cheapest_window(&prices, 0, 0), None);
+    }
+
+    #[test]
+    fn invalid_forecasts_are_rejected() {
+        let mut cache = PriceCache::new();
+        assert_eq!(cache.update(&forecast(&[])), Err(PriceError::Empty));
+        assert_eq!(
+            cache.update(&forecast(&[1.0, f32::NAN])),
+            Err(PriceError::InvalidValue)
+        );
+        assert_eq!(
+            cache.update(&forecast(&[1.0; MAX_PRICE_HOURS + 1])),
+            Err(PriceError::TooManyHours)
+        );
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn tomorrow_becomes_today() {
+        let mut cache = PriceCache::new();
+        let mut prices = [10.0; 48];
+        prices[24] = 42.0;
+        cache.update(&forecast(&prices)).unwrap();
+        assert!(cache.tick(23 * 60 + 30));
+        assert!(!cache.tick(23 * 60 + 45));
+        assert_eq!(cache.prices().len(), 48);
+        assert!(cache.tick(5));
+        assert_eq!(cache.prices().len(), 24);
+        assert_eq!(cache.current_price(), Some(42.0));
+        // without prices for tomorrow nothing is left
+        cache.tick(23 * 60);
+        cache.tick(0);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn a_forecast_after_midnight_is_for_today() {
+        let mut cache = PriceCache::new();
+        cache.update(&forecast(&[10.0; 48])).unwrap();
+        cache.tick(23 * 60 + 58);
+        // the new forecast arrives before the clock of the next message moved on
+        let mut prices = [20.0; 48];
+        prices[0] = 7.0;
+        cache.update(&forecast(&prices)).unwrap();
+        assert!(!cache.tick(5));
+        assert_eq!(cache.prices().len(), 48);
+        assert_eq!(cache.current_price(), Some(7.0));
+        assert!(cache.tick(60));
+        assert_eq!(cache.current_price(), Some(20.0));
+    }
+}
@@ -0,0 +1,211 @@
+// decodes the STAT_STATE of the senec system. the system knows about 100 states, most of them
+// are variations of the same thing, so they are grouped into what is interesting on the display
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemState {
+    Initialising,
+    Charging,
+    Discharging,
+    BatteryFull,
+    BatteryEmpty,
+    Passive,
+    OwnConsumption,
+    MaintenanceCharge,
+    Maintenance,
+    SafetyCharge,
+    Protection,
+    BackupPower,
+    Sleeping,
+    Off,
+    SoftwareUpdate,
+    Error,
+    Unknown,
+}
+
+impl SystemState {
+    pub fn from_code(code: u16) -> Self {
+        match code {
+            0 | 22 | 68 | 78 => SystemState::Initialising,
+            4 | 10 | 14 | 28 | 43 | 54 | 71 => SystemState::Charging,
+            16 | 17 | 18 | 29 | 44 => SystemState::Discharging,
+            13 => SystemState::BatteryFull,
+            15 | 76 => SystemState::BatteryEmpty,
+            19 | 30 | 31 | 32 | 42 | 56 | 69 | 85 | 87 | 88 | 89 => SystemState::Passive,
+            21 => SystemState::OwnConsumption,
+            5 | 11 | 12 | 23 | 24 => SystemState::MaintenanceCharge,
+            6 | 7 | 40 | 45 | 46 | 48 | 50 | 52 | 53 | 79 | 81 | 82 | 83 | 84 => {
+                SystemState::Maintenance
+            }
+            8 | 9 | 25 | 33 => SystemState::SafetyCharge,
+            26 | 64 | 65 => SystemState::Protection,
+            75 => SystemState::BackupPower,
+            41 => SystemState::Sleeping,
+            20 | 55 | 70 => SystemState::Off,
+            34 => SystemState::SoftwareUpdate,
+            1 | 2 | 27 | 35 | 36 | 37 | 38 | 39 | 47 | 49 | 51 | 57 | 58 | 59 | 60 | 61 | 62
+            | 63 | 66 | 67 | 72 | 73 | 74 | 77 | 80 | 86 | 90 | 91 => SystemState::Error,
+            _ => SystemState::Unknown,
+        }
+    }
+    // the server usually passes the numeric code through, but some setups send the text of
+    // the state instead
+    pub fn parse(stat_state: &str) -> Self {
+        let stat_state = stat_state.trim();
+        if let Ok(code) = stat_state.parse::<u16>() {
+            return SystemState::from_code(code);
+        }
+        // without an uppercase copy, this runs for every message
+        let upper = Uppercase(stat_state);
+        if upper.contains("ERROR") || upper.contains("FAULT") {
+            SystemState::Error
+        } else if upper.contains("MAINTENANCE CHARGE")
+            || upper.contains("EQUALIZATION: CHARGE")
+            || upper.contains("DESULFATATION: CHARGE")
+        {
+            SystemState::MaintenanceCharge
+        } else if upper.contains("MAINTENANCE") {
+            SystemState::Maintenance
+        } else if upper.contains("SAFETY CHARGE") || upper.contains("EMERGENCY CHARGE") {
+            SystemState::SafetyCharge
+        } else if upper.contains("DISCHARGE") {
+            SystemState::Discharging
+        } else if upper.contains("CHARGE") {
+            SystemState::Charging
+        } else if upper.contains("FULL") {
+            SystemState::BatteryFull
+        } else if upper.contains("EMPTY") {
+            SystemState::BatteryEmpty
+        } else if upper.contains("PASSIVE") {
+            SystemState::Passive
+        } else if upper.contains("OWN CONSUMPTION") {
+            SystemState::OwnConsumption
+        } else if upper.contains("SLEEP") {
+            SystemState::Sleeping
+        } else if upper.contains("UPDATE") {
+            SystemState::SoftwareUpdate
+        } else if upper.contains("INITIAL") {
+            SystemState::Initialising
+        } else if stat_state.eq_ignore_ascii_case("OFF") {
+            SystemState::Off
+        } else {
+            SystemState::Unknown
+        }
+    }
+    pub fn label(&self) -> &'static str {
+        match self {
+            SystemState::Initialising => "Initialising",
+            SystemState::Charging => "Charging",
+            SystemState::Discharging => "Discharging",
+            SystemState::BatteryFull => "Battery full",
+            SystemState::BatteryEmpty => "Battery empty",
+            SystemState::Passive => "Passive",
+            SystemState::OwnConsumption => "Own consumption",
+            SystemState::MaintenanceCharge => "Maintenance charge",
+            SystemState::Maintenance => "Maintenance",
+            SystemState::SafetyCharge => "Safety charge",
+            SystemState::Protection => "Protection mode",
+            SystemState::BackupPower => "Backup power",
+            SystemState::Sleeping => "Sleeping",
+            SystemState::Off => "Off",
+            SystemState::SoftwareUpdate => "Updating",
+            SystemState::Error => "Error",
+            SystemState::Unknown => "Unknown",
+        }
+    }
+    // states that need attention are drawn inverted
+    pub fn is_emphasised(&self) -> bool {
+        matches!(
+            self,
+            SystemState::Error
+                | SystemState::MaintenanceCharge
+                | SystemState::Maintenance
+                | SystemState::SafetyCharge
+        )
+    }
+}
+
+// compares case insensitive with the uppercase patterns
+struct Uppercase<'a>(&'a str);
+
+impl Uppercase<'_> {
+    fn contains(&self, pattern: &str) -> bool {
+        self.0
+            .as_bytes()
+            .windows(pattern.len())
+            .any(|window| window.eq_ignore_ascii_case(pattern.as_bytes()))
+    }
+}
+
+// gui_charging_info and gui_boosting_info are flags
+pub fn parse_flag(value: &str) -> bool {
+    matches!(value.trim(), "1" | "true" | "True" | "TRUE")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_the_codes() {
+        let cases = [
+            (0, SystemState::Initialising),
+            (4, SystemState::Charging),
+            (16, SystemState::Discharging),
+            (13, SystemState::BatteryFull),
+            (15, SystemState::BatteryEmpty),
+            (19, SystemState::Passive),
+            (21, SystemState::OwnConsumption),
+            (5, SystemState::MaintenanceCharge),
+            (6, SystemState::Maintenance),
+            (8, SystemState::SafetyCharge),
+            (26, SystemState::Protection),
+            (75, SystemState::BackupPower),
+            (41, SystemState::Sleeping),
+            (20, SystemState::Off),
+            (34, SystemState::SoftwareUpdate),
+            (1, SystemState::Error),
+            (91, SystemState::Error),
+        ];
+        for (code, state) in cases {
+            assert_eq!(SystemState::from_code(code), state, "code {}", code);
+            assert_eq!(SystemState::parse(&code.to_string()), state);
+        }
+        // there is no state 3, newer systems may know more than 91
+        assert_eq!(SystemState::from_code(3), SystemState::Unknown);
+        assert_eq!(SystemState::from_code(92), SystemState::Unknown);
+        assert_eq!(SystemState::parse(" 16 "), SystemState::Discharging);
+        assert_eq!(SystemState::Unknown.label(), "Unknown");
+    }
+
+    #[test]
+    fn decodes_the_texts() {
+        let cases = [
+            ("CHARGE", SystemState::Charging),
+            ("Discharge", SystemState::Discharging),
+            ("MAINTENANCE CHARGE", SystemState::MaintenanceCharge),
+            ("EQUALIZATION: CHARGE", SystemState::MaintenanceCharge),
+            ("EMERGENCY CHARGE", SystemState::SafetyCharge),
+            ("BATTERY FULL", SystemState::BatteryFull),
+            ("own consumption", SystemState::OwnConsumption),
+            ("ERROR: BMS FAULT", SystemState::Error),
+            ("off", SystemState::Off),
+            ("", SystemState::Unknown),
+            ("-1", SystemState::Unknown),
+            ("3.5", SystemState::Unknown),
+        ];
+        for (text, state) in cases {
+            assert_eq!(SystemState::parse(text), state, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn emphasised_states_and_flags() {
+        assert!(SystemState::Error.is_emphasised());
+        assert!(SystemState::SafetyCharge.is_emphasised());
+        assert!(!SystemState::Charging.is_emphasised());
+        assert!(parse_flag(" 1"));
+        assert!(parse_flag("true"));
+        assert!(!parse_flag("0"));
+        assert!(!parse_flag("yes"));
+    }
+}
@@ -0,0 +1,179 @@
+// diagnostics of the device for the system page. the clients fill in what they know, the ages
+// are computed once the page is drawn
+use alloc::string::String;
+use core::fmt::Write;
+use core::time::Duration;
+
+use crate::clock::Tick;
+use crate::TextBuffer;
+
+#[derive(Debug, Clone)]
+pub struct SystemInfo {
+    pub version: String,
+    pub build_hash: String,
+    pub started: Tick,
+    pub free_heap: Option<u32>,
+    pub ssid: String,
+    pub rssi: Option<i8>,
+    pub ip: Option<String>,
+    pub server: String,
+    pub last_message: Option<Tick>,
+    pub decode_errors: u32,
+    pub reconnects: u32,
+    pub full_refreshes: u32,
+    // of the lipo powering the device
+    pub battery_voltage: Option<f32>,
+}
+
+impl SystemInfo {
+    pub fn new(started: Tick) -> Self {
+        SystemInfo {
+            version: String::new(),
+            build_hash: String::new(),
+            started,
+            free_heap: None,
+            ssid: String::new(),
+            rssi: None,
+            ip: None,
+            server: String::new(),
+            last_message: None,
+            decode_errors: 0,
+            reconnects: 0,
+            full_refreshes: 0,
+            battery_voltage: None,
+        }
+    }
+    // labels and values in the order of the page, unknown values are "--". formatted on the
+    // stack, the page is drawn with every message
+    pub fn rows(&self, now: Tick) -> [(&'static str, TextBuffer); 11] {
+        let unknown = || text("--");
+        [
+            ("Uptime", format_duration(now.duration_since(self.started))),
+            (
+                "Heap",
+                self.free_heap.map(format_bytes).unwrap_or_else(unknown),
+            ),
+            ("SSID", text(&self.ssid)),
+            (
+                "RSSI",
+                self.rssi
+                    .map(|rssi| format(format_args!("{} dBm", rssi)))
+                    .unwrap_or_else(unknown),
+            ),
+            ("IP", self.ip.as_deref().map(text).unwrap_or_else(unknown)),
+            ("Server", text(&self.server)),
+            (
+                "Last msg",
+                match self.last_message {
+                    Some(last) => format(format_args!(
+                        "{} ago",
+                        format_duration(now.duration_since(last))
+                    )),
+                    None => text("never"),
+                },
+            ),
+            (
+                "Decode errors",
+                format(format_args!("{}", self.decode_errors)),
+            ),
+            ("Reconnects", format(format_args!("{}", self.reconnects))),
+            (
+                "Full refreshes",
+                format(format_args!("{}", self.full_refreshes)),
+            ),
+            (
+                "Battery",
+                self.battery_voltage
+                    .map(|voltage| format(format_args!("{:.2} V", voltage)))
+                    .unwrap_or_else(unknown),
+            ),
+        ]
+    }
+}
+
+// a value that does not fit is cut, the page has room for less anyway
+fn text(value: &str) -> TextBuffer {
+    let mut text = TextBuffer::new();
+    for c in value.chars() {
+        if text.push(c).is_err() {
+            break;
+        }
+    }
+    text
+}
+
+// the numbers always fit
+fn format(args: core::fmt::Arguments) -> TextBuffer {
+    let mut text = TextBuffer::new();
+    let _ = text.write_fmt(args);
+    text
+}
+
+// the two largest units are enough to tell what is going on
+pub fn format_duration(duration: Duration) -> TextBuffer {
+    let secs = duration.as_secs();
+    let (days, hours, minutes) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60);
+    if days > 0 {
+        format(format_args!("{}d {:02}h", days, hours))
+    } else if hours > 0 {
+        format(format_args!("{}h {:02}m", hours, minutes))
+    } else if minutes > 0 {
+        format(format_args!("{}m {:02}s", minutes, secs % 60))
+    } else {
+        format(format_args!("{}s", secs))
+    }
+}
+
+pub fn format_bytes(bytes: u32) -> TextBuffer {
+    if bytes >= 1024 * 1024 {
+        format(format_args!("{:.1} MB", bytes as f32 / (1024.0 * 1024.0)))
+    } else if bytes >= 1024 {
+        format(format_args!("{} kB", bytes / 1024))
+    } else {
+        format(format_args!("{} B", bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+        assert_eq!(format_duration(Duration::from_secs(5 * 60 + 3)), "5m 03s");
+        assert_eq!(
+            format_duration(Duration::from_secs(3 * 3600 + 4 * 60)),
+            "3h 04m"
+        );
+        assert_eq!(
+            format_duration(Duration::from_secs(2 * 86400 + 3600)),
+            "2d 01h"
+        );
+    }
+
+    #[test]
+    fn bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(183 * 1024 + 100), "183 kB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 / 2), "1.5 MB");
+    }
+
+    #[test]
+    fn rows_of_a_fresh_device() {
+        let started = Tick::default();
+        let info = SystemInfo::new(started);
+        let rows = info.rows(started + Duration::from_secs(90));
+        assert_eq!(rows[0].0, "Uptime");
+        assert_eq!(rows[0].1, "1m 30s");
+        assert_eq!(rows[1].1, "--");
+        assert_eq!(rows[6].1, "never");
+    }
+
+    #[test]
+    fn long_values_are_cut() {
+        let mut info = SystemInfo::new(Tick::default());
+        info.server = "a".repeat(100);
+        assert_eq!(info.rows(info.started)[5].1.len(), crate::TEXT_CAPACITY);
+    }
+}
@@ -0,0 +1,294 @@
+// electricity prices and what the system saved, earned and spent with them. prices are in
+// EUR per kWh
+
+use crate::metrics::MAX_SAMPLE_GAP_SECS;
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+pub const MAX_BANDS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TariffError {
+    InvalidPrice,
+    InvalidBand,
+    TooManyBands,
+}
+
+impl TariffError {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TariffError::InvalidPrice => "invalid tariff price",
+            TariffError::InvalidBand => "invalid tariff band",
+            TariffError::TooManyBands => "too many tariff bands",
+        }
+    }
+}
+
+impl core::fmt::Display for TariffError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl core::error::Error for TariffError {}
+
+// time of use band, overriding the import price. a band ending before it starts wraps around
+// midnight, one ending at 24:00 has an end_minute of 1440
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouBand {
+    pub start_minute: u16,
+    pub end_minute: u16,
+    pub import_price: f32,
+}
+
+impl TouBand {
+    pub fn contains(&self, minute_of_day: u16) -> bool {
+        if self.start_minute <= self.end_minute {
+            (self.start_minute..self.end_minute).contains(&minute_of_day)
+        } else {
+            minute_of_day >= self.start_minute || minute_of_day < self.end_minute
+        }
+    }
+    // parses "22:00-06:00@0.24"
+    pub fn parse(band: &str) -> Result<Self, TariffError> {
+        let (range, price) = band
+            .trim()
+            .split_once('@')
+            .ok_or(TariffError::InvalidBand)?;
+        let (start, end) = range.split_once('-').ok_or(TariffError::InvalidBand)?;
+        // only the end can be 24:00
+        let start_minute = parse_time(start)
+            .filter(|minute| *minute < MINUTES_PER_DAY)
+            .ok_or(TariffError::InvalidBand)?;
+        Ok(TouBand {
+            start_minute,
+            end_minute: parse_time(end).ok_or(TariffError::InvalidBand)?,
+            import_price: parse_price(price)?,
+        })
+    }
+}
+
+fn parse_time(time: &str) -> Option<u16> {
+    let (hours, minutes) = time.trim().split_once(':')?;
+    let hours: u16 = hours.parse().ok()?;
+    let minutes: u16 = minutes.parse().ok()?;
+    // 24:00 is the end of the day. the hours are checked first, so large ones can not overflow
+    if minutes >= 60 || hours > 24 || hours * 60 + minutes > MINUTES_PER_DAY {
+        return None;
+    }
+    Some(hours * 60 + minutes)
+}
+
+fn parse_price(price: &str) -> Result<f32, TariffError> {
+    match price.trim().parse::<f32>() {
+        Ok(price) if price.is_finite() && price >= 0.0 => Ok(price),
+        _ => Err(TariffError::InvalidPrice),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tariff {
+    pub import_price: f32,
+    pub feed_in: f32,
+    pub bands: heapless::Vec<TouBand, MAX_BANDS>,
+}
+
+impl Default for Tariff {
+    // a typical german tariff
+    fn default() -> Self {
+        Tariff {
+            import_price: 0.32,
+            feed_in: 0.08,
+            bands: heapless::Vec::new(),
+        }
+    }
+}
+
+impl Tariff {
+    pub fn new(import_price: f32, feed_in: f32) -> Self {
+        Tariff {
+            import_price,
+            feed_in,
+            bands: heapless::Vec::new(),
+        }
+    }
+    pub fn with_band(mut self, band: TouBand) -> Result<Self, TariffError> {
+        self.bands
+            .push(band)
+            .map_err(|_| TariffError::TooManyBands)?;
+        Ok(self)
+    }
+    // builds the tariff from the configuration strings, bands are separated by commas and can
+    // be empty
+    pub fn parse(import_price: &str, feed_in: &str, bands: &str) -> Result<Self, TariffError> {
+        let mut tariff = Tariff::new(parse_price(import_price)?, parse_price(feed_in)?);
+        for band in bands.split(',').filter(|band| !band.trim().is_empty()) {
+            tariff = tariff.with_band(TouBand::parse(band)?)?;
+        }
+        Ok(tariff)
+    }
+    // the first matching band wins
+    pub fn import_price_at(&self, minute_of_day: u16) -> f32 {
+        self.bands
+            .iter()
+            .find(|band| band.contains(minute_of_day))
+            .map(|band| band.import_price)
+            .unwrap_or(self.import_price)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Costs {
+    // value of the consumption that was covered by solar and battery
+    pub saved: f32,
+    // compensation for the energy fed into the grid
+    pub earned: f32,
+    // cost of the energy imported from the grid
+    pub spent: f32,
+}
+
+pub struct CostCalculator {
+    tariff: Tariff,
+    costs: Costs,
+    last_minute: Option<u16>,
+}
+
+impl CostCalculator {
+    pub fn new(tariff: Tariff) -> Self {
+        CostCalculator {
+            tariff,
+            costs: Costs::default(),
+            last_minute: None,
+        }
+    }
+    pub fn tariff(&self) -> &Tariff {
+        &self.tariff
+    }
+    pub fn set_tariff(&mut self, tariff: Tariff) {
+        self.tariff = tariff;
+    }
+    // grid_kw is positive when importing, house_kw is the consumption of the house
+    pub fn record(&mut self, minute_of_day: u16, grid_kw: f32, house_kw: f32, elapsed_secs: f32) {
+        if let Some(last) = self.last_minute {
+            // the clock jumped back by more than an hour, so a new day has started
+            if minute_of_day + 60 < last {
+                self.costs = Costs::default();
+            }
+        }
+        self.last_minute = Some(minute_of_day);
+        if !grid_kw.is_finite()
+            || !house_kw.is_finite()
+            || !(0.0..=MAX_SAMPLE_GAP_SECS).contains(&elapsed_secs)
+        {
+            return;
+        }
+        let hours = elapsed_secs / 3600.0;
+        let price = self.tariff.import_price_at(minute_of_day);
+        let imported = grid_kw.max(0.0) * hours;
+        let exported = (-grid_kw).max(0.0) * hours;
+        let covered = (house_kw - grid_kw.max(0.0)).max(0.0) * hours;
+
+        self.costs.spent += imported * price;
+        self.costs.earned += exported * self.tariff.feed_in;
+        self.costs.saved += covered * price;
+    }
+    pub fn costs(&self) -> Costs {
+        self.costs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn bands_wrap_around_midnight() {
+        let band = TouBand::parse("22:00-06:00@0.24").unwrap();
+        assert!(band.contains(23 * 60));
+        assert!(band.contains(5 * 60 + 59));
+        assert!(!band.contains(6 * 60));
+        assert!(!band.contains(12 * 60));
+    }
+
+    #[test]
+    fn bands_end_at_midnight() {
+        let day = TouBand::parse("00:00-24:00@0.24").unwrap();
+        assert_eq!((day.start_minute, day.end_minute), (0, MINUTES_PER_DAY));
+        assert!(day.contains(0));
+        assert!(day.contains(MINUTES_PER_DAY - 1));
+        let evening = TouBand::parse("18:00-24:00@0.24").unwrap();
+        assert!(evening.contains(23 * 60 + 59));
+        assert!(!evening.contains(0));
+        let tariff = Tariff::parse("0.30", "0.08", "0:00-24:00@0.20").unwrap();
+        assert_eq!(tariff.import_price_at(12 * 60), 0.20);
+        assert_eq!(
+            TouBand::parse("24:00-06:00@0.24"),
+            Err(TariffError::InvalidBand)
+        );
+    }
+
+    #[test]
+    fn invalid_configuration_is_rejected() {
+        assert_eq!(TouBand::parse("22:00@0.24"), Err(TariffError::InvalidBand));
+        assert_eq!(
+            TouBand::parse("25:00-06:00@0.24"),
+            Err(TariffError::InvalidBand)
+        );
+        assert_eq!(
+            TouBand::parse("2000:00-06:00@0.2"),
+            Err(TariffError::InvalidBand)
+        );
+        assert_eq!(
+            TouBand::parse("22:00-06:00@-1"),
+            Err(TariffError::InvalidPrice)
+        );
+        assert_eq!(
+            Tariff::parse("abc", "0.08", ""),
+            Err(TariffError::InvalidPrice)
+        );
+        assert_eq!(
+            Tariff::parse(
+                "0.3",
+                "0.08",
+                "0:00-1:00@0.1,1:00-2:00@0.1,2:00-3:00@0.1,3:00-4:00@0.1,4:00-5:00@0.1"
+            ),
+            Err(TariffError::TooManyBands)
+        );
+    }
+
+    #[test]
+    fn import_price_follows_the_bands() {
+        let tariff = Tariff::parse("0.30", "0.08", "22:00-06:00@0.20, 12:00-14:00@0.10").unwrap();
+        assert_eq!(tariff.import_price_at(23 * 60), 0.20);
+        assert_eq!(tariff.import_price_at(13 * 60), 0.10);
+        assert_eq!(tariff.import_price_at(18 * 60), 0.30);
+    }
+
+    #[test]
+    fn costs_are_accumulated() {
+        let tariff = Tariff::parse("0.30", "0.08", "22:00-06:00@0.20").unwrap();
+        let mut calculator = CostCalculator::new(tariff);
+        // the solar covers the house and exports 3 kW for 15 minutes
+        calculator.record(12 * 60, -3.0, 1.0, 900.0);
+        let costs = calculator.costs();
+        assert!(close(costs.earned, 0.06));
+        assert!(close(costs.saved, 0.075));
+        assert_eq!(costs.spent, 0.0);
+        // importing 2 kW for 15 minutes at night, the house uses all of it
+        calculator.record(23 * 60, 2.0, 2.0, 900.0);
+        let costs = calculator.costs();
+        assert!(close(costs.spent, 0.1));
+        assert!(close(costs.saved, 0.075));
+    }
+
+    #[test]
+    fn costs_reset_on_a_new_day() {
+        let mut calculator = CostCalculator::new(Tariff::default());
+        calculator.record(23 * 60, 2.0, 2.0, 900.0);
+        calculator.record(10, 0.0, 0.0, 0.0);
+        assert_eq!(calculator.costs(), Costs::default());
+    }
+}
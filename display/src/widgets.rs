@@ -0,0 +1,990 @@
+// the parts of the dashboard as widgets, so other dashboards can be put together from them.
+// every widget knows its spot on the stock dashboard and can be moved somewhere else, the
+// drawing code in lib.rs is shared and only shifted
+use alloc::string::String;
+use core::borrow::BorrowMut;
+use core::fmt::Debug;
+
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::mono_font::MonoTextStyleBuilder;
+use embedded_graphics::prelude::{OriginDimensions, Point, Size};
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::Pixel;
+
+use crate::color::DisplayColor;
+use crate::dashboard::{Changes, DashboardState};
+use crate::error::{DisplayError, Result};
+use crate::pages;
+use crate::panel::{CANVAS_HEIGHT, CANVAS_WIDTH};
+use crate::state::{self, SystemState};
+use crate::{ChartOptions, ConnectionDirection, DisplayBoxed, TotalsMode};
+
+pub trait Widget<T: DrawTarget> {
+    fn name(&self) -> &'static str;
+    // the area the widget draws in
+    fn bounds(&self) -> Rectangle;
+    // the size stays the same
+    fn move_to(&mut self, top_left: Point);
+    // the whole widget, the area has to be cleared before
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()>;
+    // marks the widget as dirty if the changes concern it
+    fn update(&mut self, state: &DashboardState, changes: Changes);
+    fn is_dirty(&self) -> bool;
+    // redraws a dirty widget, most of them keep their frame and only draw the values
+    fn refresh(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        self.draw(display, state)
+    }
+    // the select button
+    fn select(&mut self) {}
+}
+
+// draws every widget, one that fails does not keep the others from being drawn
+pub fn draw_all<'a, T, W>(
+    widgets: impl IntoIterator<Item = &'a mut W>,
+    display: &mut DisplayBoxed<T>,
+    state: &DashboardState,
+) -> Result<()>
+where
+    T: DrawTarget,
+    W: Widget<T> + ?Sized + 'a,
+{
+    let mut result = Ok(());
+    for widget in widgets {
+        result = result.and(widget.draw(display, state));
+    }
+    result
+}
+
+// passes the changes on and redraws the widgets that became dirty
+pub fn update_all<'a, T, W>(
+    widgets: impl IntoIterator<Item = &'a mut W>,
+    display: &mut DisplayBoxed<T>,
+    state: &DashboardState,
+    changes: Changes,
+) -> Result<()>
+where
+    T: DrawTarget,
+    W: Widget<T> + ?Sized + 'a,
+{
+    let mut result = Ok(());
+    for widget in widgets {
+        widget.update(state, changes);
+        if widget.is_dirty() {
+            result = result.and(widget.refresh(display, state));
+        }
+    }
+    result
+}
+
+// the target moved by the offset, what is drawn at the usual spot of a widget ends up where it
+// was moved to. what the drawing code puts outside of the widget is dropped
+pub struct Shifted<'a, T> {
+    target: &'a mut T,
+    offset: Point,
+    // before the shift
+    clip: Rectangle,
+}
+
+impl<'a, T> Shifted<'a, T> {
+    pub fn new(target: &'a mut T, offset: Point, clip: Rectangle) -> Self {
+        Shifted {
+            target,
+            offset,
+            clip,
+        }
+    }
+}
+
+impl<T: DrawTarget + OriginDimensions> DrawTarget for Shifted<'_, T> {
+    type Color = T::Color;
+    type Error = T::Error;
+    fn draw_iter<I>(&mut self, pixels: I) -> core::result::Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let (offset, clip) = (self.offset, self.clip);
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .filter(|Pixel(point, _)| clip.contains(*point))
+                .map(|Pixel(point, color)| Pixel(point + offset, color)),
+        )
+    }
+    fn fill_solid(
+        &mut self,
+        area: &Rectangle,
+        color: Self::Color,
+    ) -> core::result::Result<(), Self::Error> {
+        let area = area.intersection(&self.clip);
+        self.target.fill_solid(
+            &Rectangle::new(area.top_left + self.offset, area.size),
+            color,
+        )
+    }
+}
+
+impl<T: OriginDimensions> OriginDimensions for Shifted<'_, T> {
+    fn size(&self) -> Size {
+        self.target.size()
+    }
+}
+
+// runs the body with the display shifted by the offset. a widget in its usual spot draws on the
+// display itself, a moved one through a view on the stack, neither allocates
+macro_rules! shifted {
+    ($display:expr, $offset:expr, $bounds:expr, |$d:ident| $body:expr) => {{
+        let offset: Point = $offset;
+        if offset == Point::zero() {
+            let $d = &mut *$display;
+            $body
+        } else {
+            let mut shifted = Shifted::new(&mut *$display.0, offset, $bounds);
+            let $d = &mut DisplayBoxed::borrowed(&mut shifted);
+            $body
+        }
+    }};
+}
+
+// where the widget is on the stock dashboard, at moves it somewhere else
+macro_rules! widget_bounds {
+    ($widget:ident, $bounds:expr) => {
+        impl $widget {
+            pub const BOUNDS: Rectangle = $bounds;
+            pub fn at(mut self, top_left: Point) -> Self {
+                self.offset = top_left - Self::BOUNDS.top_left;
+                self
+            }
+        }
+    };
+}
+
+// the bounds, the spot and the dirty flag, the same for every widget
+macro_rules! placement {
+    ($bounds:expr) => {
+        fn bounds(&self) -> Rectangle {
+            let bounds: Rectangle = $bounds;
+            Rectangle::new(bounds.top_left + self.offset, bounds.size)
+        }
+        fn move_to(&mut self, top_left: Point) {
+            let bounds: Rectangle = $bounds;
+            self.offset = top_left - bounds.top_left;
+        }
+        fn is_dirty(&self) -> bool {
+            self.dirty
+        }
+    };
+}
+
+// the circles of the house, the inverter, the grid and the battery with the connections between
+// them, the time of the last message and the wallbox
+#[derive(Debug, Default)]
+pub struct FlowWidget {
+    offset: Point,
+    dirty: bool,
+}
+
+widget_bounds!(
+    FlowWidget,
+    Rectangle::new(Point::new(12, 0), Size::new(138, 128))
+);
+
+impl<T> Widget<T> for FlowWidget
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn name(&self) -> &'static str {
+        "flow"
+    }
+    placement!(Self::BOUNDS);
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        self.dirty = false;
+        shifted!(display, self.offset, Self::BOUNDS, |display| {
+            let style = MonoTextStyleBuilder::new()
+                .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
+                .text_color(T::Color::BLACK)
+                .build();
+            display.draw_default_flow(style)?;
+            draw_flow(display, state)
+        })
+    }
+    fn update(&mut self, _state: &DashboardState, changes: Changes) {
+        self.dirty |= changes.flow;
+    }
+    fn refresh(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        self.dirty = false;
+        shifted!(display, self.offset, Self::BOUNDS, |display| draw_flow(
+            display, state
+        ))
+    }
+}
+
+// the values in the circles, the connections and the ev node
+fn draw_flow<T, B>(display: &mut DisplayBoxed<T, B>, state: &DashboardState) -> Result<()>
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+    B: BorrowMut<T>,
+{
+    let style = MonoTextStyleBuilder::new()
+        .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
+        .text_color(T::Color::BLACK)
+        .build();
+    let ui = &state.ui;
+    // borrowed from the state and formatted on the stack, this runs on every message
+    fn value(v: &Option<String>) -> &str {
+        v.as_deref().unwrap_or("0.00")
+    }
+    let house_pow = value(&ui.gui_house_pow);
+    let bat_charge = value(&ui.gui_bat_data_fuel_charge);
+    let inverter_pow = value(&ui.gui_inverter_power);
+    let grid_pow = value(&ui.gui_grid_pow);
+    let bat_power = value(&ui.gui_bat_data_power);
+    let ts = ui.ts.as_deref().unwrap_or("0:00");
+
+    display.clear_text()?;
+    display.draw_text(
+        style,
+        house_pow,
+        &if bat_power != "0.00" && !bat_power.starts_with('-') {
+            // bat_power is the current going to the battery, therefore if non 0 and not
+            // starting with a minus we are charging
+            text!("+{}", bat_charge)?
+        } else if bat_power.starts_with('-') && bat_power != "-0.00" {
+            // in this case we are discharging
+            text!("-{}", bat_charge)?
+        } else {
+            // no current flowing in or out of the battery
+            text!("{}", bat_charge)?
+        },
+        inverter_pow,
+        &match grid_pow.starts_with('-') {
+            true => text!("{}", grid_pow)?,
+            false => text!("+{}", grid_pow)?,
+        },
+        ts,
+    )?;
+
+    // clearing the connections
+    display.fill_solid(
+        &Rectangle::new(Point::new(54, 43), Size::new(42, 41)),
+        T::Color::WHITE,
+    )?;
+    // to the house always active
+    display.draw_connections(ConnectionDirection::Top(true))?;
+
+    if bat_power != "0.00" && !bat_power.starts_with('-') {
+        display.draw_connections(ConnectionDirection::Bottom(true))?;
+    } else if bat_power != "0.00" && bat_power.starts_with('-') {
+        display.draw_connections(ConnectionDirection::Bottom(false))?;
+    }
+
+    if grid_pow != "0.00" && !grid_pow.starts_with('-') {
+        display.draw_connections(ConnectionDirection::Right(false))?;
+    } else if grid_pow != "0.00" && grid_pow.starts_with('-') {
+        display.draw_connections(ConnectionDirection::Right(true))?;
+    }
+
+    // the inverter only produces, a negative value is its own consumption
+    if inverter_pow != "0.00" && inverter_pow != "-0.00" {
+        display.draw_connections(ConnectionDirection::Left(false))?;
+    }
+
+    // the ev node sits on top of the cleared connections, so it is redrawn every time
+    display.update_wallbox(ui.wallbox.as_ref())
+}
+
+// the state of charge in the top left corner
+#[derive(Debug, Default)]
+pub struct BatteryWidget {
+    offset: Point,
+    dirty: bool,
+}
+
+widget_bounds!(
+    BatteryWidget,
+    Rectangle::new(Point::new(0, 0), Size::new(32, 17))
+);
+
+impl<T> Widget<T> for BatteryWidget
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn name(&self) -> &'static str {
+        "battery"
+    }
+    placement!(Self::BOUNDS);
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        shifted!(display, self.offset, Self::BOUNDS, |display| display
+            .draw_default_battery_percentage())?;
+        self.refresh(display, state)
+    }
+    fn update(&mut self, _state: &DashboardState, changes: Changes) {
+        self.dirty |= changes.flow;
+    }
+    // the placeholder stays until the first value arrived
+    fn refresh(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        self.dirty = false;
+        let Some(charge) = state.ui.gui_bat_data_fuel_charge.as_deref() else {
+            return Ok(());
+        };
+        let charge: f32 = charge
+            .trim()
+            .parse()
+            .map_err(|_| DisplayError::InvalidInput)?;
+        let percentage = text!("{:.0}", charge.clamp(0.0, 100.0))?;
+        shifted!(display, self.offset, Self::BOUNDS, |display| display
+            .update_battery_percentage(&percentage))
+    }
+}
+
+// the state of the senec system and whether it charges or boosts
+#[derive(Debug, Default)]
+pub struct StatusWidget {
+    offset: Point,
+    dirty: bool,
+}
+
+widget_bounds!(
+    StatusWidget,
+    Rectangle::new(Point::new(0, 18), Size::new(53, 24))
+);
+
+impl<T> Widget<T> for StatusWidget
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn name(&self) -> &'static str {
+        "status"
+    }
+    placement!(Self::BOUNDS);
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        self.dirty = false;
+        let ui = &state.ui;
+        shifted!(display, self.offset, Self::BOUNDS, |display| display
+            .update_system_state(
+                SystemState::parse(ui.stat_state.as_deref().unwrap_or("")),
+                state::parse_flag(ui.gui_charging_info.as_deref().unwrap_or("0")),
+                state::parse_flag(ui.gui_boosting_info.as_deref().unwrap_or("0")),
+            ))
+    }
+    fn update(&mut self, _state: &DashboardState, changes: Changes) {
+        self.dirty |= changes.flow;
+    }
+}
+
+// autarky and self consumption of the day with their gauges
+#[derive(Debug, Default)]
+pub struct AutarkyWidget {
+    offset: Point,
+    dirty: bool,
+}
+
+widget_bounds!(
+    AutarkyWidget,
+    Rectangle::new(Point::new(0, 87), Size::new(53, 28))
+);
+
+impl<T> Widget<T> for AutarkyWidget
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn name(&self) -> &'static str {
+        "autarky"
+    }
+    placement!(Self::BOUNDS);
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        self.dirty = false;
+        shifted!(display, self.offset, Self::BOUNDS, |display| display
+            .update_self_sufficiency(
+                state.balance.autarky(),
+                state.balance.self_consumption(),
+            ))
+    }
+    fn update(&mut self, _state: &DashboardState, changes: Changes) {
+        self.dirty |= changes.flow;
+    }
+}
+
+// the energy of the day, or what it cost, depending on the totals mode
+#[derive(Debug, Default)]
+pub struct TotalsWidget {
+    offset: Point,
+    dirty: bool,
+}
+
+widget_bounds!(
+    TotalsWidget,
+    Rectangle::new(Point::new(100, 88), Size::new(46, 40))
+);
+
+impl<T> Widget<T> for TotalsWidget
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn name(&self) -> &'static str {
+        "totals"
+    }
+    placement!(Self::BOUNDS);
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        shifted!(
+            display,
+            self.offset,
+            Self::BOUNDS,
+            |display| match state.totals_mode {
+                TotalsMode::Energy => display.new_total("00.00", "00.00"),
+                TotalsMode::Cost => display.new_total_cost(),
+            }
+        )?;
+        self.refresh(display, state)
+    }
+    fn update(&mut self, state: &DashboardState, changes: Changes) {
+        // the costs follow the power flow
+        self.dirty |= match state.totals_mode {
+            TotalsMode::Energy => changes.totals,
+            TotalsMode::Cost => changes.flow,
+        };
+    }
+    fn refresh(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        self.dirty = false;
+        shifted!(
+            display,
+            self.offset,
+            Self::BOUNDS,
+            |display| match state.totals_mode {
+                TotalsMode::Energy => match &state.ui.total_data {
+                    Some(total_data) => {
+                        display.update_total_new(&total_data.consumption, &total_data.generated)
+                    }
+                    None => Ok(()),
+                },
+                TotalsMode::Cost => display.update_total_cost(state.costs.costs()),
+            }
+        )
+    }
+}
+
+// the hourly weather, or the price forecast while there are prices
+#[derive(Debug, Default)]
+pub struct WeatherWidget {
+    offset: Point,
+    dirty: bool,
+    // the prices ran out, the weather grid has to come back
+    cleared: bool,
+}
+
+widget_bounds!(
+    WeatherWidget,
+    Rectangle::new(Point::new(150, 17), Size::new(146, 52))
+);
+
+impl<T> Widget<T> for WeatherWidget
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn name(&self) -> &'static str {
+        "weather"
+    }
+    placement!(Self::BOUNDS);
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        self.cleared = false;
+        shifted!(display, self.offset, Self::BOUNDS, |display| display
+            .draw_default_weather_grid())?;
+        self.refresh(display, state)
+    }
+    fn update(&mut self, state: &DashboardState, changes: Changes) {
+        self.cleared |= changes.prices_cleared;
+        self.dirty |= if state.prices.is_empty() {
+            changes.weather || changes.prices_cleared
+        } else {
+            changes.prices
+        };
+    }
+    fn refresh(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        self.dirty = false;
+        let cleared = core::mem::take(&mut self.cleared);
+        let weather = state.ui.weather.as_ref();
+        shifted!(display, self.offset, Self::BOUNDS, |display| {
+            if cleared {
+                display.clear_price_forecast()?;
+            }
+            // the price strip takes the place of the hourly weather while there are prices
+            if !state.prices.is_empty() {
+                display.update_price_forecast(&state.prices)
+            } else if let Some(hourly) = weather.and_then(|weather| weather.hourly.as_ref()) {
+                display.update_weather_data(hourly)
+            } else {
+                Ok(())
+            }
+        })
+    }
+}
+
+// the times of sunrise and sunset
+#[derive(Debug, Default)]
+pub struct SunWidget {
+    offset: Point,
+    dirty: bool,
+}
+
+widget_bounds!(
+    SunWidget,
+    Rectangle::new(Point::new(150, 0), Size::new(146, 16))
+);
+
+impl<T> Widget<T> for SunWidget
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn name(&self) -> &'static str {
+        "sun"
+    }
+    placement!(Self::BOUNDS);
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        shifted!(display, self.offset, Self::BOUNDS, |display| display
+            .draw_default_sun())?;
+        self.refresh(display, state)
+    }
+    fn update(&mut self, _state: &DashboardState, changes: Changes) {
+        self.dirty |= changes.sun;
+    }
+    fn refresh(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        self.dirty = false;
+        let weather = state.ui.weather.as_ref();
+        let Some(daily) = weather.and_then(|weather| weather.daily.as_ref()) else {
+            return Ok(());
+        };
+        let sunrise = daily.sunrise.first().ok_or(DisplayError::MissingData)?;
+        let sunset = daily.sunset.first().ok_or(DisplayError::MissingData)?;
+        shifted!(display, self.offset, Self::BOUNDS, |display| display
+            .update_sun_data(sunrise, sunset))
+    }
+}
+
+// the predicted and the actual generation of the day, select toggles the gridlines
+#[derive(Debug, Default)]
+pub struct ChartWidget {
+    offset: Point,
+    dirty: bool,
+    options: ChartOptions,
+}
+
+widget_bounds!(
+    ChartWidget,
+    Rectangle::new(Point::new(149, 72), Size::new(147, 56))
+);
+
+impl ChartWidget {
+    pub fn with_options(mut self, options: ChartOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl<T> Widget<T> for ChartWidget
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn name(&self) -> &'static str {
+        "chart"
+    }
+    placement!(Self::BOUNDS);
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        self.dirty = false;
+        let options = self.options;
+        shifted!(
+            display,
+            self.offset,
+            Self::BOUNDS,
+            |display| match &state.prediction {
+                Ok(columns) => display.update_chart(columns, &state.actual, options),
+                Err(e) => display.draw_chart_status(e.label()),
+            }
+        )
+    }
+    fn update(&mut self, _state: &DashboardState, changes: Changes) {
+        self.dirty |= changes.chart;
+    }
+    fn select(&mut self) {
+        self.options.gridlines = !self.options.gridlines;
+        self.dirty = true;
+    }
+}
+
+// the stock dashboard in one piece, for the layouts of the larger panels. the parts keep their
+// spots on it and are moved along with it
+#[derive(Debug, Default)]
+pub struct DashboardWidget {
+    offset: Point,
+    // the dot of the connection is part of the frame
+    dirty: bool,
+    flow: FlowWidget,
+    battery: BatteryWidget,
+    status: StatusWidget,
+    autarky: AutarkyWidget,
+    totals: TotalsWidget,
+    sun: SunWidget,
+    weather: WeatherWidget,
+    chart: ChartWidget,
+}
+
+impl DashboardWidget {
+    pub const BOUNDS: Rectangle = PAGE;
+    pub fn at(mut self, top_left: Point) -> Self {
+        self.shift(top_left - Self::BOUNDS.top_left);
+        self
+    }
+    fn shift(&mut self, offset: Point) {
+        self.offset = offset;
+        self.flow.offset = offset;
+        self.battery.offset = offset;
+        self.status.offset = offset;
+        self.autarky.offset = offset;
+        self.totals.offset = offset;
+        self.sun.offset = offset;
+        self.weather.offset = offset;
+        self.chart.offset = offset;
+    }
+    // borrowed, so the dashboard does not allocate on every message
+    fn parts<T>(&mut self) -> [&mut dyn Widget<T>; 8]
+    where
+        T: DrawTarget + OriginDimensions,
+        T::Color: DisplayColor,
+        T::Error: Debug,
+    {
+        [
+            &mut self.flow,
+            &mut self.battery,
+            &mut self.status,
+            &mut self.autarky,
+            &mut self.totals,
+            // the icons of the sun reach into the weather, which has to win
+            &mut self.sun,
+            &mut self.weather,
+            &mut self.chart,
+        ]
+    }
+}
+
+impl<T> Widget<T> for DashboardWidget
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn name(&self) -> &'static str {
+        "dashboard"
+    }
+    fn bounds(&self) -> Rectangle {
+        Rectangle::new(Self::BOUNDS.top_left + self.offset, Self::BOUNDS.size)
+    }
+    fn move_to(&mut self, top_left: Point) {
+        self.shift(top_left - Self::BOUNDS.top_left);
+    }
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        self.dirty = false;
+        shifted!(display, self.offset, Self::BOUNDS, |display| display
+            .draw_dashboard_frame())?;
+        if state.connected {
+            shifted!(display, self.offset, Self::BOUNDS, |display| display
+                .set_connected())?;
+        }
+        draw_all(self.parts(), display, state)
+    }
+    fn update(&mut self, state: &DashboardState, changes: Changes) {
+        self.dirty |= changes.connection && state.connected;
+        for part in self.parts::<T>() {
+            part.update(state, changes);
+        }
+    }
+    fn is_dirty(&self) -> bool {
+        self.dirty
+            || self.flow.dirty
+            || self.battery.dirty
+            || self.status.dirty
+            || self.autarky.dirty
+            || self.totals.dirty
+            || self.sun.dirty
+            || self.weather.dirty
+            || self.chart.dirty
+    }
+    fn refresh(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        if self.dirty {
+            self.dirty = false;
+            shifted!(display, self.offset, Self::BOUNDS, |display| display
+                .set_connected())?;
+        }
+        update_all(self.parts(), display, state, Changes::default())
+    }
+    // toggles the gridlines of the chart
+    fn select(&mut self) {
+        Widget::<T>::select(&mut self.chart);
+    }
+}
+
+// the detail pages as widgets, the larger panels show them next to the dashboard
+const PAGE: Rectangle = Rectangle::new(Point::zero(), Size::new(CANVAS_WIDTH, CANVAS_HEIGHT));
+
+// the phase page
+#[derive(Debug, Default)]
+pub struct PhasePageWidget {
+    offset: Point,
+    dirty: bool,
+}
+
+widget_bounds!(PhasePageWidget, PAGE);
+
+impl<T> Widget<T> for PhasePageWidget
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn name(&self) -> &'static str {
+        "phase_page"
+    }
+    placement!(Self::BOUNDS);
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        self.dirty = false;
+        shifted!(display, self.offset, Self::BOUNDS, |display| {
+            pages::draw_phases(display, state)
+        })
+    }
+    fn update(&mut self, _state: &DashboardState, changes: Changes) {
+        self.dirty |= changes.phases;
+    }
+}
+
+// the battery page
+#[derive(Debug, Default)]
+pub struct BatteryPageWidget {
+    offset: Point,
+    dirty: bool,
+}
+
+widget_bounds!(BatteryPageWidget, PAGE);
+
+impl<T> Widget<T> for BatteryPageWidget
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn name(&self) -> &'static str {
+        "battery_page"
+    }
+    placement!(Self::BOUNDS);
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        self.dirty = false;
+        shifted!(display, self.offset, Self::BOUNDS, |display| display
+            .draw_battery_page(&state.battery))
+    }
+    fn update(&mut self, _state: &DashboardState, changes: Changes) {
+        self.dirty |= changes.battery;
+    }
+}
+
+// the system page, redrawn with every message like the page so the ages stay current
+#[derive(Debug, Default)]
+pub struct SystemPageWidget {
+    offset: Point,
+    dirty: bool,
+}
+
+widget_bounds!(SystemPageWidget, PAGE);
+
+impl<T> Widget<T> for SystemPageWidget
+where
+    T: DrawTarget + OriginDimensions,
+    T::Color: DisplayColor,
+    T::Error: Debug,
+{
+    fn name(&self) -> &'static str {
+        "system_page"
+    }
+    placement!(Self::BOUNDS);
+    fn draw(&mut self, display: &mut DisplayBoxed<T>, state: &DashboardState) -> Result<()> {
+        self.dirty = false;
+        shifted!(display, self.offset, Self::BOUNDS, |display| display
+            .draw_system_page(&state.system, state.now))
+    }
+    fn update(&mut self, _state: &DashboardState, _changes: Changes) {
+        self.dirty = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // remembers where was drawn in black, the fills around the values may reach further
+    struct Touched {
+        points: Vec<Point>,
+    }
+
+    impl DrawTarget for Touched {
+        type Color = epd_waveshare::color::Color;
+        type Error = std::convert::Infallible;
+        fn draw_iter<I>(&mut self, pixels: I) -> core::result::Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            for Pixel(point, color) in pixels {
+                if color == Self::Color::Black {
+                    self.points.push(point);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl OriginDimensions for Touched {
+        fn size(&self) -> Size {
+            Size::new(296, 128)
+        }
+    }
+
+    fn touched() -> DisplayBoxed<Touched> {
+        DisplayBoxed::new(Touched { points: Vec::new() })
+    }
+
+    #[test]
+    fn widgets_stay_in_their_bounds() {
+        let state = DashboardState::default();
+        let mut widgets: Vec<Box<dyn Widget<Touched>>> = vec![
+            Box::new(FlowWidget::default()),
+            Box::new(BatteryWidget::default()),
+            Box::new(StatusWidget::default()),
+            Box::new(AutarkyWidget::default()),
+            Box::new(TotalsWidget::default()),
+            Box::new(WeatherWidget::default()),
+            Box::new(SunWidget::default()),
+            Box::new(ChartWidget::default()),
+        ];
+        for widget in widgets.iter_mut() {
+            let mut display = touched();
+            widget.draw(&mut display, &state).unwrap();
+            let bounds = widget.bounds();
+            assert!(
+                !display.0.points.is_empty(),
+                "{} drew nothing",
+                widget.name()
+            );
+            // the totals box reaches past the bottom of the canvas, which drops that row
+            let canvas = Rectangle::new(Point::zero(), Size::new(296, 128));
+            for point in display.0.points.iter().filter(|p| canvas.contains(**p)) {
+                assert!(
+                    bounds.contains(*point),
+                    "{} drew at {:?}",
+                    widget.name(),
+                    point
+                );
+            }
+
+            // moved, nothing is left outside of the new bounds
+            widget.move_to(Point::new(0, 0));
+            let mut display = touched();
+            widget.draw(&mut display, &state).unwrap();
+            let bounds = widget.bounds();
+            assert_eq!(bounds.top_left, Point::zero());
+            assert!(display.0.points.iter().all(|point| bounds.contains(*point)));
+        }
+    }
+
+    #[test]
+    fn only_the_concerned_widgets_are_redrawn() {
+        let state = DashboardState::default();
+        let mut display = touched();
+        let mut sun = SunWidget::default();
+        let mut chart = ChartWidget::default();
+        let mut widgets: [&mut dyn Widget<Touched>; 2] = [&mut sun, &mut chart];
+        update_all(
+            widgets.iter_mut().map(|widget| &mut **widget),
+            &mut display,
+            &state,
+            Changes {
+                chart: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(!display.0.points.is_empty());
+        assert!(widgets.iter().all(|widget| !widget.is_dirty()));
+
+        // nothing changed, nothing is drawn
+        display.0.points.clear();
+        update_all(
+            widgets.iter_mut().map(|widget| &mut **widget),
+            &mut display,
+            &state,
+            Changes::default(),
+        )
+        .unwrap();
+        assert!(display.0.points.is_empty());
+
+        widgets[1].select();
+        assert!(widgets[1].is_dirty());
+    }
+
+    #[test]
+    fn the_dashboard_and_the_pages_move_in_one_piece() {
+        let mut state = DashboardState::default();
+        state.connected = true;
+        let mut widgets: Vec<Box<dyn Widget<Touched>>> = vec![
+            Box::new(DashboardWidget::default().at(Point::new(10, 20))),
+            Box::new(PhasePageWidget::default().at(Point::new(10, 20))),
+            Box::new(BatteryPageWidget::default().at(Point::new(10, 20))),
+            Box::new(SystemPageWidget::default().at(Point::new(10, 20))),
+        ];
+        for widget in widgets.iter_mut() {
+            let mut display = touched();
+            widget.draw(&mut display, &state).unwrap();
+            let bounds = widget.bounds();
+            assert_eq!(bounds.top_left, Point::new(10, 20));
+            assert!(
+                !display.0.points.is_empty(),
+                "{} drew nothing",
+                widget.name()
+            );
+            assert!(
+                display.0.points.iter().all(|point| bounds.contains(*point)),
+                "{} drew outside",
+                widget.name()
+            );
+        }
+
+        // the parts of the dashboard are redrawn on their own
+        let dashboard = &mut widgets[0];
+        dashboard.update(
+            &state,
+            Changes {
+                chart: true,
+                ..Default::default()
+            },
+        );
+        assert!(dashboard.is_dirty());
+        let mut display = touched();
+        dashboard.refresh(&mut display, &state).unwrap();
+        assert!(!dashboard.is_dirty());
+        let chart = ChartWidget::BOUNDS;
+        let chart = Rectangle::new(chart.top_left + Point::new(10, 20), chart.size);
+        assert!(!display.0.points.is_empty());
+        assert!(display.0.points.iter().all(|point| chart.contains(*point)));
+    }
+}
@@ -0,0 +1,303 @@
+// the dashboard runs for weeks on the esp32, where every allocation fragments the heap a bit
+// more. once the first message has been drawn, the messages after it must not allocate while
+// they are merged and drawn. this needs its own test binary, since it swaps the allocator
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::convert::Infallible;
+use std::time::Duration;
+
+use display::clock::Tick;
+use display::dashboard::DashboardState;
+use display::layout::PageLayout;
+use display::night::NightConfig;
+use display::pages::{BatteryPage, DashboardPage, PageManager, PhasePage, SystemPage};
+use display::prototypes::types::data::Oneof;
+use display::prototypes::types::*;
+use display::DisplayBoxed;
+use embedded_graphics::draw_target::DrawTarget;
+use embedded_graphics::prelude::{OriginDimensions, Size};
+use embedded_graphics::Pixel;
+use epd_waveshare::color::Color;
+
+// counts per thread, so the test harness and other tests don't get in the way
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count() {
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let result = f();
+    (result, ALLOCATIONS.with(Cell::get) - before)
+}
+
+struct NullTarget;
+
+impl DrawTarget for NullTarget {
+    type Color = Color;
+    type Error = Infallible;
+    fn draw_iter<I>(&mut self, _pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        Ok(())
+    }
+}
+
+impl OriginDimensions for NullTarget {
+    fn size(&self) -> Size {
+        Size::new(296, 128)
+    }
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+// a full message like the server sends it, the values change a little every time
+fn ui_message(minute: u32) -> Data {
+    let ui = NewUiStruct {
+        ts: Some(format!("{:02}:{:02}", 10 + minute / 60, minute % 60)),
+        stat_state: Some("CHARGE".to_string()),
+        gui_bat_data_power: Some(format!("{}.{:02}", 1 + minute % 2, minute % 100)),
+        gui_inverter_power: Some(format!("3.{:02}", minute % 100)),
+        gui_house_pow: Some("0.85".to_string()),
+        gui_grid_pow: Some(format!("-0.{:02}", minute % 100)),
+        gui_bat_data_fuel_charge: Some("64.0".to_string()),
+        gui_charging_info: Some("1".to_string()),
+        gui_boosting_info: Some("0".to_string()),
+        weather: Some(WeatherNew {
+            hourly: Some(HourlyNew {
+                time: strings(&["10:00", "11:00", "12:00", "13:00"]),
+                temperature_2m: strings(&["14.1", "15.0", "15.8", "16.2"]),
+                rain: strings(&["0.0", "0.1", "0.0", "0.0"]),
+                showers: strings(&["0.0", "0.0", "0.0", "0.0"]),
+                cloud_cover: strings(&["20", "35", "50", "40"]),
+                uv_index: strings(&["2.1", "3.0", "3.4", "3.1"]),
+                uv_index_clear_sky: strings(&["2.5", "3.3", "3.8", "3.5"]),
+            }),
+            daily: Some(DailyNew {
+                time: strings(&["2024-05-01"]),
+                sunset: strings(&["20:41"]),
+                sunrise: strings(&["06:02"]),
+            }),
+        }),
+        total_data: Some(TotalDataNew {
+            consumption: "5.21".to_string(),
+            generated: "12.07".to_string(),
+            new: true,
+        }),
+        wallbox: Some(WallboxNew {
+            present: true,
+            state: "charging".to_string(),
+            charging_power: "7.20".to_string(),
+            session_energy: "4.10".to_string(),
+            car_connected: true,
+        }),
+        phases: Some(PhaseData {
+            grid_power: vec![-0.1, -0.2, -0.1],
+            grid_voltage: vec![230.1, 231.0, 229.8],
+            inverter_power: vec![1.0, 1.0, 1.0],
+            grid_frequency: 50.01,
+        }),
+        battery: Some(BatteryData {
+            voltage: 51.2,
+            current: 20.0,
+            temperature: 24.5,
+            cycles: 312,
+            state_of_health: 97.0,
+            min_cell_voltage: 3.2,
+            max_cell_voltage: 3.3,
+            capacity_kwh: 10.0,
+        }),
+    };
+    Data {
+        oneof: Some(Oneof::UiData(ui)),
+    }
+}
+
+fn prediction() -> Data {
+    Data {
+        oneof: Some(Oneof::Prediction(Prediction {
+            prediction: (0..24i32)
+                .map(|hour| (12 - hour).abs() * -50 + 600)
+                .collect(),
+            interval_minutes: Some(60),
+            start_minute: Some(0),
+            scale: Some(1000.0),
+        })),
+    }
+}
+
+#[test]
+fn the_steady_state_does_not_allocate() {
+    let mut display = DisplayBoxed::new(NullTarget);
+    let mut state = DashboardState::default();
+    state.connected = true;
+    let mut pages = PageManager::new(Box::new(DashboardPage::new()))
+        .with_page(Box::new(PhasePage))
+        .with_page(Box::new(BatteryPage));
+    let start = Tick::default();
+    let changes = state.apply(&prediction(), start);
+    pages.draw(&mut display, &state).unwrap();
+    pages.update(&mut display, &state, changes).unwrap();
+    // the banner of a notice is drawn with every update
+    let notice = Data {
+        oneof: Some(Oneof::Notice(Notice {
+            title: "Maintenance".to_string(),
+            body: "the inverter is serviced on monday".to_string(),
+            severity: Severity::Warning.into(),
+            expires_secs: None,
+        })),
+    };
+    let changes = state.apply(&notice, start);
+    pages.update(&mut display, &state, changes).unwrap();
+
+    // the first message fills the state, after that only the values change
+    for minute in 0..10 {
+        let now = start + Duration::from_secs(minute as u64 * 60);
+        let message = ui_message(minute);
+        let (_, count) = allocations(|| {
+            let changes = state.apply(&message, now);
+            pages.update(&mut display, &state, changes).unwrap();
+            pages.tick(now, &mut display, &state).unwrap();
+        });
+        if minute > 0 {
+            assert_eq!(count, 0, "allocations for the message of minute {}", minute);
+        }
+    }
+
+    // a full repaint, like after a reconnect, does not allocate either
+    let (_, count) = allocations(|| pages.draw(&mut display, &state).unwrap());
+    assert_eq!(count, 0);
+
+    // and neither does the card of a critical notice
+    let critical = Data {
+        oneof: Some(Oneof::Notice(Notice {
+            title: "Power cut".to_string(),
+            body: "the grid is switched off from 9:00 to 12:00 for works on the line".to_string(),
+            severity: Severity::Critical.into(),
+            expires_secs: None,
+        })),
+    };
+    state.apply(&critical, start);
+    let (_, count) = allocations(|| pages.draw(&mut display, &state).unwrap());
+    assert_eq!(count, 0);
+}
+
+// the widgets of a layout are drawn somewhere else than on the stock dashboard
+#[test]
+fn moved_widgets_do_not_allocate() {
+    let layout = PageLayout::parse(
+        "[[widget]]\n\
+         type = \"chart\"\n\
+         region = [0, 0, 147, 56]\n\
+         gridlines = true\n\
+         [[widget]]\n\
+         type = \"flow\"\n\
+         region = [150, 0, 138, 128]\n\
+         [[widget]]\n\
+         type = \"weather\"\n\
+         region = [0, 60, 146, 52]\n",
+    )
+    .unwrap();
+    let mut display = DisplayBoxed::new(NullTarget);
+    let mut state = DashboardState::default();
+    state.connected = true;
+    let mut pages = PageManager::new(Box::new(layout.page()));
+    let start = Tick::default();
+    let changes = state.apply(&prediction(), start);
+    pages.draw(&mut display, &state).unwrap();
+    pages.update(&mut display, &state, changes).unwrap();
+
+    for minute in 0..10 {
+        let now = start + Duration::from_secs(minute as u64 * 60);
+        let message = ui_message(minute);
+        let (_, count) = allocations(|| {
+            let changes = state.apply(&message, now);
+            pages.update(&mut display, &state, changes).unwrap();
+        });
+        if minute > 0 {
+            assert_eq!(count, 0, "allocations for the message of minute {}", minute);
+        }
+    }
+    let (_, count) = allocations(|| pages.draw(&mut display, &state).unwrap());
+    assert_eq!(count, 0);
+}
+
+// the system page is drawn again with every message, so the ages of its rows stay current
+#[test]
+fn the_system_page_does_not_allocate() {
+    let mut display = DisplayBoxed::new(NullTarget);
+    let mut state = DashboardState::default();
+    state.connected = true;
+    state.system.ip = Some("192.168.1.23".to_string());
+    state.system.free_heap = Some(183 * 1024);
+    let mut pages = PageManager::new(Box::new(SystemPage));
+    let start = Tick::default();
+    pages.draw(&mut display, &state).unwrap();
+
+    for minute in 0..10 {
+        let now = start + Duration::from_secs(minute as u64 * 60);
+        let message = ui_message(minute);
+        let (_, count) = allocations(|| {
+            let changes = state.apply(&message, now);
+            pages.update(&mut display, &state, changes).unwrap();
+        });
+        if minute > 0 {
+            assert_eq!(count, 0, "allocations for the message of minute {}", minute);
+        }
+    }
+}
+
+// at night the summary is drawn from scratch with every message
+#[test]
+fn the_night_summary_does_not_allocate() {
+    let mut display = DisplayBoxed::new(NullTarget);
+    let mut state = DashboardState::default();
+    state.connected = true;
+    state
+        .night
+        .set_config(NightConfig::parse("00:00-23:59", "").unwrap());
+    let mut pages = PageManager::new(Box::new(DashboardPage::new()));
+    let start = Tick::default();
+    let changes = state.apply(&ui_message(0), start);
+    pages.update(&mut display, &state, changes).unwrap();
+    let changes = state.tick(start);
+    assert!(state.night.is_active());
+    pages.update(&mut display, &state, changes).unwrap();
+
+    for minute in 1..10 {
+        let now = start + Duration::from_secs(minute as u64 * 60);
+        let message = ui_message(minute);
+        let (_, count) = allocations(|| {
+            let mut changes = state.apply(&message, now);
+            changes.night |= state.tick(now).night;
+            pages.update(&mut display, &state, changes).unwrap();
+        });
+        assert!(state.night.is_active());
+        assert_eq!(count, 0, "allocations for the message of minute {}", minute);
+    }
+}
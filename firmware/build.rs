@@ -23,6 +23,46 @@ fn main() -> std::io::Result<()> {
             println!("cargo:rustc-env=SERVER_ADDR={}", addr);
         }
     }
+    // the tariff, the mode of the totals box, the page rotation, the alert rules and the night
+    // mode are optional, the firmware falls back to the defaults if they are not set
+    for optional in [
+        "TARIFF_IMPORT",
+        "TARIFF_FEED_IN",
+        "TARIFF_BANDS",
+        "TOTALS_MODE",
+        "PAGE_ROTATION_SECS",
+        "ALERT_RULES",
+        "QUIET_HOURS",
+        "NIGHT_REFRESH_MINS",
+    ] {
+        if let Ok(value) = std::env::var(optional) {
+            println!("cargo:rustc-env={}={}", optional, value);
+        }
+    }
+    // the panel the firmware is built for, the 2.9" v2 if not set
+    let panels = ["2in9", "2in9_v2", "2in9bc", "4in2", "7in5"];
+    let panel = std::env::var("PANEL").unwrap_or("2in9_v2".to_string());
+    if !panels.contains(&panel.as_str()) {
+        panic!(
+            "Error unknown PANEL {}! Please use one of {}",
+            panel,
+            panels.join(", ")
+        );
+    }
+    println!(
+        "cargo:rustc-check-cfg=cfg(panel, values(\"{}\"))",
+        panels.join("\", \"")
+    );
+    println!("cargo:rustc-cfg=panel=\"{}\"", panel);
+    // the short hash of the commit, shown on the system page
+    let hash = std::process::Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .unwrap_or("unknown".to_string());
+    println!("cargo:rustc-env=GIT_HASH={}", hash.trim());
     println!("cargo:rustc-env=CRATE_CC_NO_DEFAULTS=1");
 
     embuild::espidf::sysenv::output();
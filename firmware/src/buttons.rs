@@ -0,0 +1,60 @@
+// the two push buttons of the enclosure. both pull their pin to ground when pressed, the
+// gestures are detected in display::buttons
+use std::time::Duration;
+
+use display::buttons::{Button, ButtonConfig, Gesture};
+use display::clock::Tick;
+use display::pages::InputEvent;
+use esp_idf_hal::gpio::{Gpio5, Gpio6, Input, PinDriver, Pull};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonAction {
+    Input(InputEvent),
+    FullRefresh,
+    FactoryReset,
+}
+
+pub struct Buttons<'a> {
+    pin_a: PinDriver<'a, Gpio5, Input>,
+    pin_b: PinDriver<'a, Gpio6, Input>,
+    a: Button,
+    b: Button,
+}
+
+impl<'a> Buttons<'a> {
+    pub fn new(gpio5: Gpio5, gpio6: Gpio6) -> anyhow::Result<Self> {
+        let mut pin_a = PinDriver::input(gpio5)?;
+        pin_a.set_pull(Pull::Up)?;
+        let mut pin_b = PinDriver::input(gpio6)?;
+        pin_b.set_pull(Pull::Up)?;
+        Ok(Buttons {
+            pin_a,
+            pin_b,
+            a: Button::new(ButtonConfig::default()),
+            // a factory reset should not happen by accident
+            b: Button::new(ButtonConfig {
+                long_press: Duration::from_secs(5),
+                ..Default::default()
+            }),
+        })
+    }
+    // a: short next page, double previous page, long full refresh
+    // b: short select, long factory reset
+    pub fn poll(&mut self, now: Tick) -> Option<ButtonAction> {
+        // both are sampled every time, so neither misses an edge
+        let a = match self.a.update(self.pin_a.is_low(), now) {
+            Some(Gesture::Short) => Some(ButtonAction::Input(InputEvent::Next)),
+            Some(Gesture::Double) => Some(ButtonAction::Input(InputEvent::Previous)),
+            Some(Gesture::Long) => Some(ButtonAction::FullRefresh),
+            None => None,
+        };
+        let b = match self.b.update(self.pin_b.is_low(), now) {
+            Some(Gesture::Short) | Some(Gesture::Double) => {
+                Some(ButtonAction::Input(InputEvent::Select))
+            }
+            Some(Gesture::Long) => Some(ButtonAction::FactoryReset),
+            None => None,
+        };
+        a.or(b)
+    }
+}
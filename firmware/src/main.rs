@@ -1,57 +1,39 @@
+pub mod buttons;
 pub mod client;
+pub mod panel;
+pub mod settings;
 pub mod types;
 pub mod wifi;
 
+use display::alerts;
+use display::clock::Tick;
+use display::color::DisplayColor;
+use display::dashboard::{Changes, DashboardState};
+use display::layout::PageLayout;
+use display::night::NightConfig;
+use display::pages::{BatteryPage, DashboardPage, PageManager, PhasePage, SystemPage};
 use display::prototypes::types::*;
+use display::system::SystemInfo;
+use display::tariff::Tariff;
 use display::DisplayBoxed;
+use display::TotalsMode;
 use embedded_graphics::draw_target::DrawTarget;
 use embedded_graphics::mono_font::MonoTextStyleBuilder;
 use embedded_graphics::prelude::Point;
-use embedded_graphics::prelude::Size;
-use embedded_graphics::primitives::Rectangle;
 use embedded_graphics::text::{Text, TextStyleBuilder};
 use embedded_graphics::Drawable;
-use epd_waveshare::prelude::WaveshareDisplay;
-use epd_waveshare::prelude::*;
 use prost::Message;
 use std::time::Duration;
 
 use anyhow::anyhow;
-use display::ConnectionDirection;
-use epd_waveshare::epd2in9_v2;
-use epd_waveshare::epd2in9_v2::Epd2in9;
 use esp_idf_hal::peripherals::Peripherals;
+use esp_idf_svc::nvs::EspDefaultNvsPartition;
 
+use crate::buttons::{ButtonAction, Buttons};
+use crate::panel::{Active, PanelColor, PanelDisplay, PanelDriver};
+use crate::settings::SettingsStore;
 use crate::wifi::connect_to_wifi;
-use display::prototypes::types::data::Oneof;
-
-#[derive(Debug)]
-struct PrevText {
-    house_pow: String,
-    bat_charge: String,
-    inverter_pow: String,
-    grid_pow: String,
-    ts: String,
-}
-impl Default for PrevText {
-    fn default() -> Self {
-        PrevText {
-            house_pow: "0.00".to_string(),
-            bat_charge: "0.00".to_string(),
-            inverter_pow: "0.00".to_string(),
-            grid_pow: "0.00".to_string(),
-            ts: "0:00".to_string(),
-        }
-    }
-}
-#[derive(Debug)]
-struct PrevConnections {
-    battery: String,
-    grid: String,
-    sun_inverter: String,
-}
-use esp_idf_hal::delay;
-use esp_idf_hal::delay::Ets;
+use display::panel::Panel;
 use esp_idf_hal::gpio;
 
 use esp_idf_hal::gpio::Gpio10;
@@ -60,12 +42,7 @@ use esp_idf_hal::gpio::Gpio18;
 use esp_idf_hal::gpio::Gpio21;
 use esp_idf_hal::gpio::Gpio38;
 use esp_idf_hal::gpio::Gpio48;
-use esp_idf_hal::gpio::Input;
-use esp_idf_hal::gpio::Output;
-use esp_idf_hal::gpio::PinDriver;
 use esp_idf_hal::spi;
-use esp_idf_hal::spi::SpiDeviceDriver;
-use esp_idf_hal::spi::SpiDriver;
 use esp_idf_hal::spi::SPI2;
 use esp_idf_hal::units::Hertz;
 
@@ -77,19 +54,8 @@ pub fn init_display<'a>(
     gpio10: Gpio10,
     gpio18: Gpio18,
     gpio17: Gpio17,
-) -> anyhow::Result<(
-    DisplayBoxed<epd2in9_v2::Display2in9>,
-    Epd2in9<
-        SpiDeviceDriver<'a, SpiDriver<'a>>,
-        //PinDriver<'a, Gpio21, Output>,
-        PinDriver<'a, Gpio10, Input>,
-        PinDriver<'a, Gpio18, Output>,
-        PinDriver<'a, Gpio17, Output>,
-        Ets,
-    >,
-    SpiDeviceDriver<'a, SpiDriver<'a>>,
-)> {
-    let mut driver = spi::SpiDeviceDriver::new_single(
+) -> anyhow::Result<(PanelDisplay, PanelDriver<'a>)> {
+    let driver = spi::SpiDeviceDriver::new_single(
         spi2,
         gpio48,
         gpio38,
@@ -107,20 +73,48 @@ pub fn init_display<'a>(
 
     let rst = gpio::PinDriver::output(gpio17)?;
 
-    let epd = match epd2in9_v2::Epd2in9::new(&mut driver, busy, dc, rst, &mut delay::Ets, None) {
-        std::result::Result::Ok(epd) => epd,
-        Err(e) => return Err(anyhow::Error::new(e)),
-    };
+    let panel = PanelDriver::new(driver, busy, dc, rst)?;
+
+    // the panel in landscape, the pages are at the top in the middle of the larger panels
+    let mut dis_boxed = DisplayBoxed::new(Active::canvas());
 
-    let display = Box::new(epd2in9_v2::Display2in9::default());
-    let mut dis_boxed = DisplayBoxed { 0: display };
+    dis_boxed.clear(PanelColor::WHITE)?;
+    return Ok((dis_boxed, panel));
+}
+
+// erases what the device stored in the nvs, like the wifi state, and starts over. the
+// configuration from the .env is compiled in and stays
+fn factory_reset() -> anyhow::Result<()> {
+    log::info!("factory reset");
+    esp_idf_svc::sys::esp!(unsafe { esp_idf_svc::sys::nvs_flash_erase() })?;
+    esp_idf_hal::reset::restart();
+}
 
-    dis_boxed.0.set_rotation(DisplayRotation::Rotate90);
-    dis_boxed.clear(epd_waveshare::color::Color::White.into())?;
-    return Ok((dis_boxed, epd, driver));
+// the values of the system page that change all the time
+fn update_system_info(info: &mut SystemInfo) {
+    info.free_heap = Some(unsafe { esp_idf_svc::sys::esp_get_free_heap_size() });
+    let mut ap_info = esp_idf_svc::sys::wifi_ap_record_t::default();
+    info.rssi =
+        esp_idf_svc::sys::esp!(unsafe { esp_idf_svc::sys::esp_wifi_sta_get_ap_info(&mut ap_info) })
+            .ok()
+            .map(|_| ap_info.rssi);
+}
+
+// bad data only spoils the page until the next message or full repaint, so it is logged and the
+// loop goes on. a display that fails ends main
+fn recover<T: Default>(result: display::error::Result<T>) -> anyhow::Result<T> {
+    match result {
+        Err(err) if err.is_recoverable() => {
+            println!("error drawing the page: {}", err);
+            Ok(T::default())
+        }
+        result => Ok(result?),
+    }
 }
 
 fn main() -> anyhow::Result<()> {
+    // the clock of the dashboard counts from here, the uptime of the system page as well
+    let started = std::time::Instant::now();
     let wifi_password = option_env!("WIFI_PASS").ok_or(anyhow!("wifi_pass not set"))?;
     let wifi_ssid = option_env!("WIFI_SSID").ok_or(anyhow!("wifi_ssid not set"))?;
     let server_addr = option_env!("SERVER_ADDR").ok_or(anyhow!("server_addr not set"))?;
@@ -132,7 +126,7 @@ fn main() -> anyhow::Result<()> {
     let peripherals = Peripherals::take()?;
 
     // setting up display
-    let (mut display, mut epd, mut driver) = init_display(
+    let (mut display, mut panel) = init_display(
         peripherals.spi2,
         peripherals.pins.gpio48,
         peripherals.pins.gpio38,
@@ -141,43 +135,124 @@ fn main() -> anyhow::Result<()> {
         peripherals.pins.gpio18,
         peripherals.pins.gpio17,
     )?;
-    log::info!("Got the display");
+    log::info!("Got the display: {}", Active::SPEC.name);
+
+    let mut buttons = Buttons::new(peripherals.pins.gpio5, peripherals.pins.gpio6)?;
 
     // connecting to wifi
     display.draw_status_message("Connecting to Wifi")?;
-    epd.update_and_display_frame(&mut driver, display.buffer(), &mut delay::Ets)?;
-    epd.update_old_frame(&mut driver, display.buffer(), &mut delay::Ets)?;
+    panel.full_refresh(&display)?;
 
-    let mut _wifi = connect_to_wifi(peripherals.modem, wifi_ssid, wifi_password)?;
+    // the wifi keeps its state in the nvs, next to the settings from the server
+    let nvs = EspDefaultNvsPartition::take()?;
+    let mut store = SettingsStore::new(nvs.clone())?;
+    let mut _wifi = connect_to_wifi(peripherals.modem, nvs, wifi_ssid, wifi_password)?;
 
     display.clear_status_message()?;
     display.draw_status_message("Wifi success")?;
-    epd.update_new_frame(&mut driver, display.buffer(), &mut delay::Ets)?;
-    epd.display_new_frame(&mut driver, &mut delay::Ets)?;
+    panel.partial_refresh(&display)?;
 
     let default_text_style = MonoTextStyleBuilder::new()
         .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
-        .text_color(Color::Black)
+        .text_color(PanelColor::BLACK)
         .build();
     let _text_style_baseline = TextStyleBuilder::new()
         .baseline(embedded_graphics::text::Baseline::Top)
         .build();
 
+    // everything the pages show, this survives reconnects. the tariff and the mode of the
+    // totals box come from the .env
+    let totals_mode = TotalsMode::parse(option_env!("TOTALS_MODE").unwrap_or(""));
+    let tariff = match option_env!("TARIFF_IMPORT") {
+        Some(import) => Tariff::parse(
+            import,
+            option_env!("TARIFF_FEED_IN").unwrap_or("0.08"),
+            option_env!("TARIFF_BANDS").unwrap_or(""),
+        )
+        .unwrap_or_else(|err| {
+            log::info!("Error in tariff configuration, using the default: {}", err);
+            Tariff::default()
+        }),
+        None => Tariff::default(),
+    };
+    let mut state = DashboardState::new(tariff, totals_mode);
+    state.system = SystemInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        build_hash: env!("GIT_HASH").to_string(),
+        ssid: wifi_ssid.to_string(),
+        ip: _wifi
+            .wifi()
+            .sta_netif()
+            .get_ip_info()
+            .ok()
+            .map(|info| info.ip.to_string()),
+        server: server_addr.to_string(),
+        // the lipo is not connected to an adc yet
+        battery_voltage: None,
+        ..SystemInfo::new(Tick::default())
+    };
+
+    // rules like "battery<10,import>5@10m,error,nodata@15m", see alerts::Rule::parse. the ones
+    // the server sent last come before the .env
+    let rules = store
+        .load(settings::ALERT_RULES)
+        .or_else(|| option_env!("ALERT_RULES").map(str::to_string));
+    if let Some(rules) = rules {
+        match alerts::rules_or_default(&rules) {
+            Ok(rules) => state.alerts.set_rules(rules),
+            Err(err) => log::info!("Error in alert rules, using the default: {}", err),
+        }
+    }
+    // the night follows the sun unless quiet hours like "22:00-06:30" are set
+    match NightConfig::parse(
+        option_env!("QUIET_HOURS").unwrap_or(""),
+        option_env!("NIGHT_REFRESH_MINS").unwrap_or(""),
+    ) {
+        Ok(config) => state.night.set_config(config),
+        Err(err) => log::info!("Error in night configuration, using the default: {}", err),
+    }
+
+    // only the active page is drawn, the others catch up once they are shown
+    let mut pages = PageManager::new(Box::new(DashboardPage::new()))
+        .with_page(Box::new(PhasePage))
+        .with_page(Box::new(BatteryPage))
+        .with_page(Box::new(SystemPage));
+    if let Some(secs) = option_env!("PAGE_ROTATION_SECS").and_then(|secs| secs.parse().ok()) {
+        pages = pages.with_rotation(Duration::from_secs(secs));
+    }
+    // the larger panels show the detail pages around the dashboard
+    match PageLayout::for_panel(&Active::SPEC) {
+        Some(Ok(layout)) => pages.set_page(0, Box::new(layout.page())),
+        Some(Err(err)) => log::info!("Error in the layout of the panel: {}", err),
+        None => {}
+    }
+    // the layout the server sent last replaces the dashboard until it sends a new one
+    state.panel_size = Active::SPEC.size();
+    if let Some(config) = store.load(settings::LAYOUT) {
+        match PageLayout::parse_for(&config, state.panel_size) {
+            Ok(layout) => {
+                pages.set_page(0, Box::new(layout.page()));
+                state.layout = Some(layout);
+            }
+            Err(err) => log::info!("Error in the stored layout, using the default: {}", err),
+        }
+    }
+
     let mut retries = 0;
     'outer: loop {
         log::info!("Retry: {}", retries);
+        state.system.reconnects = retries;
         if retries > 5 {
             break;
         }
         // Clear the display from any remainders
         display.clear_status_message()?;
-        epd.update_new_frame(&mut driver, display.buffer(), &mut delay::Ets)?;
-        epd.display_new_frame(&mut driver, &mut delay::Ets)?;
+        panel.partial_refresh(&display)?;
 
-        // drawing default interface
-        display.draw_default_display(default_text_style)?;
-        epd.update_and_display_frame(&mut driver, display.buffer(), &mut delay::Ets)?;
-        epd.update_old_frame(&mut driver, display.buffer(), &mut delay::Ets)?;
+        // drawing the active page with what we know so far
+        state.connected = false;
+        recover(pages.draw(&mut display, &state))?;
+        panel.full_refresh(&display)?;
 
         let (mut socket, response) =
             tungstenite::connect(format!("ws://{}/subscribe", server_addr))?;
@@ -187,302 +262,198 @@ fn main() -> anyhow::Result<()> {
             continue;
         }
         log::info!("Connected to websocket");
-        display.set_connected()?;
-        epd.update_new_frame(&mut driver, display.buffer(), &mut delay::Ets)?;
-        epd.display_new_frame(&mut driver, &mut delay::Ets)?;
-        epd.update_old_frame(&mut driver, display.buffer(), &mut delay::Ets)?;
+        state.connected = true;
+        recover(pages.update(
+            &mut display,
+            &state,
+            Changes {
+                connection: true,
+                ..Default::default()
+            },
+        ))?;
+        panel.partial_refresh(&display)?;
+
+        // reads only block shortly, so the buttons can be sampled in between the messages
+        if let tungstenite::stream::MaybeTlsStream::Plain(stream) = socket.get_mut() {
+            stream.set_read_timeout(Some(Duration::from_millis(10)))?;
+        }
 
         //start time
         let mut curr_time = std::time::SystemTime::now();
 
-        // we store the prediction results buffer and preallocate
-        let mut rescaled = [0.0; 288];
-
-        // we make sure that we repaint fully, if it has been fully flushed
-        let mut flushed = true;
-
-        // if there was an error before we need to repaint the default display
-        let mut prev_error = false;
-
-        let mut prevs = PrevText::default();
-        let mut prev_connections = PrevConnections {
-            battery: "0.00".to_string(),
-            grid: "0.00".to_string(),
-            sun_inverter: "0.00".to_string(),
-        };
-
-        let mut repaints = 0;
         'inner: loop {
             if retries > 5 {
                 break 'outer;
             }
 
+            let now = Tick::since(started);
+            // the banner is shown or removed without a message as well
+            let mut changes = state.tick(now);
+            let action = buttons.poll(now);
+            // any press keeps the night away for a while, the press that ends it or closes a
+            // notice does nothing else
+            let mut closed = false;
+            if action.is_some() {
+                let woken = state.wake(now);
+                changes.night |= woken.night;
+                changes.notice |= woken.notice;
+                closed = woken.notice;
+            }
+            match action {
+                _ if changes.night || closed => {}
+                Some(ButtonAction::Input(event)) => {
+                    recover(pages.handle(event, &mut display, &state, Tick::since(started)))?;
+                    println!("showing page: {}", pages.title());
+                    panel.refresh_now(&display)?;
+                }
+                Some(ButtonAction::FullRefresh) => {
+                    println!("full repaint on request");
+                    recover(pages.draw(&mut display, &state))?;
+                    panel.full_refresh(&display)?;
+                    state.system.full_refreshes += 1;
+                    curr_time = std::time::SystemTime::now();
+                }
+                Some(ButtonAction::FactoryReset) => {
+                    display.clear(PanelColor::WHITE)?;
+                    display.draw_status_message("Factory reset")?;
+                    panel.full_refresh(&display)?;
+                    factory_reset()?;
+                }
+                None => {}
+            }
+            if changes.night {
+                // switching between the pages and the summary is a full refresh, the panel
+                // sleeps for the rest of the night
+                recover(pages.update(&mut display, &state, changes))?;
+                if !state.night.is_active() {
+                    println!("night mode ended");
+                    panel.wake_up()?;
+                }
+                panel.full_refresh(&display)?;
+                state.system.full_refreshes += 1;
+                curr_time = std::time::SystemTime::now();
+                if state.night.is_active() {
+                    println!("night mode started");
+                    panel.sleep()?;
+                    state.night.refreshed(now);
+                }
+            } else if state.night.is_active() {
+                // the summary is kept up to date by the messages, but only shown every so
+                // often. alerts and notices do not wait for that
+                let overlays = changes.alert || changes.notice;
+                if overlays {
+                    recover(pages.update(&mut display, &state, changes))?;
+                }
+                if overlays || state.night.refresh_due(now) {
+                    panel.wake_up()?;
+                    panel.full_refresh(&display)?;
+                    panel.sleep()?;
+                    state.system.full_refreshes += 1;
+                    state.night.refreshed(now);
+                }
+            } else {
+                let overlays = changes.alert || changes.notice;
+                if overlays {
+                    recover(pages.update(&mut display, &state, changes))?;
+                }
+                if overlays || recover(pages.tick(now, &mut display, &state))? {
+                    panel.partial_refresh(&display)?;
+                }
+                // the panels without a quick refresh catch up with the values that came
+                panel.refresh_pending(&display)?;
+            }
+
             match socket.read() {
                 Ok(message) => match message {
+                    // plain text is shown as a notice, at night the panel wakes up for it
                     tungstenite::Message::Text(t) => {
-                        println!("got a text message: {:?}", t);
+                        let changes = state.show_text(&t, Tick::since(started));
+                        if let Some(invalid) = changes.invalid {
+                            println!("invalid text message: {}", invalid);
+                            continue;
+                        }
+                        recover(pages.update(&mut display, &state, changes))?;
+                        if state.night.is_active() {
+                            panel.wake_up()?;
+                            panel.full_refresh(&display)?;
+                            panel.sleep()?;
+                            state.system.full_refreshes += 1;
+                            state.night.refreshed(now);
+                        } else {
+                            panel.refresh_now(&display)?;
+                        }
                         continue;
                     }
                     tungstenite::Message::Binary(b) => {
-                        match Data::decode(b) {
-                            Ok(data_enum) => match data_enum.oneof {
-                                Some(Oneof::UiData(data)) => {
-                                    println!("got ui data: {:?}", data);
-                                    let time_now = std::time::SystemTime::now();
-                                    let since = time_now.duration_since(curr_time)?;
-                                    // every 2 mins we do a full repaint, refresh of the display to clean up
-                                    // small imperfections in the pixles that occur due to quick refreshes
-                                    if since > Duration::from_secs(120) {
-                                        // the issue with disapperaing weather information was:
-                                        // the displays buffer is cleared( which
-                                        // contains the weather information and then the prev
-                                        // buffer is painted ) but when the next repaint comes
-                                        // around the prev buffer no longer contains the weather
-                                        // information so we lose that information
-                                        println!("full repaint {}", repaints);
-                                        // we have to copy the displays buffer to be able to
-                                        // repaint the same display after a full fresh up
-                                        let last_buff = display.buffer();
-                                        let prev_buffer = last_buff.to_vec();
-
-                                        //then we clear the displys buffer + paint the default
-                                        //structures
-                                        display.clear(Color::White)?;
-                                        display.draw_default_display(default_text_style)?;
-
-                                        // now we reset the old buffer
-                                        display.set_buf(&prev_buffer)?;
-                                        // in the case that the screen was an error screen before we need to
-                                        // repaint the default display
-                                        if prev_error {
-                                            println!("repainting default display");
-                                            epd.update_and_display_frame(
-                                                &mut driver,
-                                                display.buffer(),
-                                                &mut delay::Ets,
-                                            )?;
-                                        } else {
-                                            epd.update_and_display_frame(
-                                                &mut driver,
-                                                prev_buffer.as_slice(),
-                                                &mut delay::Ets,
-                                            )?;
-                                        }
-
-                                        repaints += 1;
-
-                                        epd.update_old_frame(
-                                            &mut driver,
-                                            display.buffer(),
-                                            &mut delay::Ets,
-                                        )?;
-                                        curr_time = time_now;
-                                        flushed = true;
-                                    }
-
-                                    display.clear_text()?;
-
-                                    // very verbose incoming
-                                    // we could solve this by creating more granular drawing
-                                    // functionality, so we only have to draw the smallest parts
-                                    // that change, but this will be extremely verbose and might
-                                    // not really matter performance wise
-
-                                    let house_pow = match &data.gui_house_pow {
-                                        Some(v) => {
-                                            prevs.house_pow = v.clone();
-                                            v
-                                        }
-                                        None => &prevs.house_pow,
-                                    };
-                                    let bat_charge = match &data.gui_bat_data_fuel_charge {
-                                        Some(v) => {
-                                            prevs.bat_charge = v.clone();
-                                            v
-                                        }
-                                        None => &prevs.bat_charge,
-                                    };
-                                    let inverter_pow = match &data.gui_inverter_power {
-                                        Some(v) => {
-                                            prevs.inverter_pow = v.clone();
-                                            v
-                                        }
-                                        None => &prevs.inverter_pow,
-                                    };
-                                    let grid_pow = match &data.gui_grid_pow {
-                                        Some(v) => {
-                                            prevs.grid_pow = v.clone();
-                                            v
-                                        }
-                                        None => &prevs.grid_pow,
-                                    };
-                                    let ts = match &data.ts {
-                                        Some(v) => {
-                                            prevs.ts = v.clone();
-                                            v
-                                        }
-                                        None => &prevs.ts,
-                                    };
-                                    let bat_power = match &data.gui_bat_data_power {
-                                        Some(v) => {
-                                            prev_connections.battery = v.to_string();
-                                            v
-                                        }
-                                        None => prev_connections.battery.as_str(),
-                                    };
-                                    display.draw_text(
-                                        default_text_style,
-                                        &house_pow,
-                                        &if bat_power != "0.00" && !bat_power.starts_with("-") {
-                                            // bat_power is the current going to the battery,
-                                            // therefore if non 0 and not starting with a minus we
-                                            // are charging
-                                            format!("+{}", bat_charge)
-                                        } else if bat_power.starts_with("-") && bat_power != "-0.00"
-                                        {
-                                            // in this case we are discharging
-                                            format!("-{}", bat_charge)
-                                        } else {
-                                            // no current flowing in or out of the battery
-                                            format!("{}", bat_charge)
-                                        },
-                                        &inverter_pow,
-                                        &match grid_pow.starts_with("-") {
-                                            true => format!("{}", grid_pow),
-                                            false => format!("+{}", grid_pow),
-                                        },
-                                        &ts,
-                                    )?;
-
-                                    // clearing the connections
-                                    display.fill_solid(
-                                        &Rectangle::new(Point::new(54, 43), Size::new(42, 41)),
-                                        Color::White,
-                                    )?;
-                                    // to the house always active
-                                    display.draw_connections(ConnectionDirection::Top(true))?;
-
-                                    // we could also just check for changes and clear the arrows
-                                    // individually to avoid this copying and redrawing
-                                    // will do that in the future
-
-                                    let grid = match &data.gui_grid_pow {
-                                        Some(v) => {
-                                            prev_connections.grid = v.clone();
-                                            v
-                                        }
-                                        None => &prev_connections.grid,
-                                    };
-                                    let sun_inverter = match &data.gui_inverter_power {
-                                        Some(v) => {
-                                            prev_connections.sun_inverter = v.clone();
-                                            v
-                                        }
-                                        None => &prev_connections.sun_inverter,
-                                    };
-
-                                    if bat_power != "0.00" && !bat_power.starts_with("-") {
-                                        display
-                                            .draw_connections(ConnectionDirection::Bottom(true))?;
-                                    } else if bat_power != "0.00" && bat_power.starts_with("-") {
-                                        display
-                                            .draw_connections(ConnectionDirection::Bottom(false))?;
-                                    }
-
-                                    if grid != "0.00" && !grid.starts_with("-") {
-                                        display
-                                            .draw_connections(ConnectionDirection::Right(false))?;
-                                    } else if grid != "0.00" && grid.starts_with("-") {
-                                        display
-                                            .draw_connections(ConnectionDirection::Right(true))?;
-                                    }
-
-                                    if sun_inverter != "0.00" && !sun_inverter.starts_with("-") {
-                                        display
-                                            .draw_connections(ConnectionDirection::Left(false))?;
-                                    } else if sun_inverter != "-0.00"
-                                        && sun_inverter.starts_with("-")
-                                    {
-                                        display
-                                            .draw_connections(ConnectionDirection::Left(false))?;
-                                    }
-
-                                    if let Some(weather) = data.weather {
-                                        if let Some(daily) = weather.daily {
-                                            let sunrise = daily
-                                                .sunrise
-                                                .first()
-                                                .ok_or(anyhow!("missing sunrise values"))?;
-                                            let sunset = daily
-                                                .sunset
-                                                .first()
-                                                .ok_or(anyhow!("missing sunset values"))?;
-                                            display.update_sun_data(sunrise, sunset)?;
-                                        }
-                                        if let Some(hourly) = weather.hourly {
-                                            display.update_weather_data(hourly)?;
-                                        }
-                                    }
-                                    if let Some(total_data) = data.total_data {
-                                        if total_data.new || flushed {
-                                            display.update_total_new(
-                                                &total_data.consumption,
-                                                &total_data.generated,
-                                            )?;
-                                        }
-                                    }
-                                    if flushed {
-                                        display.update_chart(&rescaled)?;
-                                    }
-
-                                    flushed = false;
-                                    epd.update_new_frame(
-                                        &mut driver,
-                                        display.buffer(),
-                                        &mut delay::Ets,
-                                    )?;
-                                    epd.display_new_frame(&mut driver, &mut delay::Ets)?;
-                                    epd.update_old_frame(
-                                        &mut driver,
-                                        display.buffer(),
-                                        &mut delay::Ets,
-                                    )?;
-                                    prev_error = false;
-
-                                    continue;
-                                }
-                                Some(Oneof::Prediction(prediction)) => {
-                                    println!("got prediction: {:?}", prediction);
-                                    if prediction.prediction.len() != 288 {
-                                        continue;
-                                    }
-                                    // rescaling the values. over the wire the values were encoded using i32
-                                    // instead of f32. since the values are in the range of 0-12 this saves
-                                    // stream bandwidth
-                                    for (i, v) in prediction.prediction.iter().enumerate() {
-                                        rescaled[i] = *v as f32 / 1000.0;
-                                    }
-                                    display.update_chart(&rescaled)?;
-                                    epd.update_new_frame(
-                                        &mut driver,
-                                        display.buffer(),
-                                        &mut delay::Ets,
-                                    )?;
-                                    epd.display_new_frame(&mut driver, &mut delay::Ets)?;
-                                    continue;
-                                }
-                                None => {
-                                    println!("no data present");
-                                }
-                            },
+                        let data_enum = match Data::decode(b) {
+                            Ok(data_enum) => data_enum,
                             Err(e) => {
-                                prev_error = true;
+                                state.system.decode_errors += 1;
                                 println!("error decoding data: {:?}", e);
                                 continue;
                             }
                         };
-
-                        println!("error parsing the message");
+                        println!("got data: {:?}", data_enum);
+                        let changes = state.apply(&data_enum, Tick::since(started));
+                        update_system_info(&mut state.system);
+                        if let Some(invalid) = changes.invalid {
+                            println!("invalid data: {}", invalid);
+                        }
+                        // the settings of the server outlast a restart
+                        let setting = match &data_enum.oneof {
+                            Some(data::Oneof::Layout(layout)) if changes.layout => {
+                                Some((settings::LAYOUT, &layout.config))
+                            }
+                            Some(data::Oneof::AlertRules(rules)) if changes.alert_rules => {
+                                Some((settings::ALERT_RULES, &rules.rules))
+                            }
+                            _ => None,
+                        };
+                        if let Some((key, value)) = setting {
+                            if let Err(err) = store.save(key, value) {
+                                println!("error storing the {}: {}", key, err);
+                            }
+                        }
+                        // the panel sleeps, the next night refresh shows the new values. an
+                        // alert raised by the message or a notice is shown right away
+                        if state.night.is_active() {
+                            recover(pages.update(&mut display, &state, changes))?;
+                            if changes.alert || changes.notice {
+                                panel.wake_up()?;
+                                panel.full_refresh(&display)?;
+                                panel.sleep()?;
+                                state.system.full_refreshes += 1;
+                                state.night.refreshed(now);
+                            }
+                            continue;
+                        }
+
+                        let time_now = std::time::SystemTime::now();
+                        let since = time_now.duration_since(curr_time)?;
+                        // every 2 mins we do a full repaint, refresh of the display to clean up
+                        // small imperfections in the pixles that occur due to quick refreshes.
+                        // the page is drawn from the state, so nothing is lost on the way. a new
+                        // layout or picture has to go through update, that sets up its page
+                        let replaced = changes.layout || changes.bitmap;
+                        if since > Duration::from_secs(120) && !replaced {
+                            println!("full repaint {}", state.system.full_refreshes);
+                            recover(pages.draw(&mut display, &state))?;
+                            panel.full_refresh(&display)?;
+                            state.system.full_refreshes += 1;
+                            curr_time = time_now;
+                            continue;
+                        }
+
+                        recover(pages.update(&mut display, &state, changes))?;
+                        // the server asks for it after large changes of its pictures
+                        if changes.full_refresh {
+                            panel.full_refresh(&display)?;
+                            state.system.full_refreshes += 1;
+                            curr_time = time_now;
+                        } else {
+                            panel.partial_refresh(&display)?;
+                        }
+                        continue;
                     }
                     tungstenite::Message::Close(v) => {
                         println!("connection was closed: {:?}", v);
@@ -493,6 +464,15 @@ fn main() -> anyhow::Result<()> {
                         continue;
                     }
                 },
+                // nothing arrived within the timeout
+                Err(tungstenite::Error::Io(e))
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    continue;
+                }
                 Err(e) => {
                     println!("error reading from ws: {:?}", e);
                     retries += 1;
@@ -501,19 +481,23 @@ fn main() -> anyhow::Result<()> {
             }
         }
         retries += 1;
-        display.clear(Color::White)?;
+        // the panel could still be asleep from the night
+        if state.night.is_active() {
+            panel.wake_up()?;
+        }
+        display.clear(PanelColor::WHITE)?;
         Text::new(
             &format!("Disconnected from Websocket! Retry: {}", retries),
             Point::new(45, 40),
             default_text_style,
         )
         .draw(&mut display)?;
-        epd.update_and_display_frame(&mut driver, display.buffer(), &mut delay::Ets)?;
+        panel.full_refresh(&display)?;
 
         continue;
     }
 
-    display.clear(Color::White)?;
+    display.clear(PanelColor::WHITE)?;
     Text::new(
         "Disconnected from Websocket!",
         Point::new(60, 40),
@@ -526,7 +510,7 @@ fn main() -> anyhow::Result<()> {
         default_text_style,
     )
     .draw(&mut display)?;
-    epd.update_and_display_frame(&mut driver, display.buffer(), &mut delay::Ets)?;
+    panel.full_refresh(&display)?;
 
     Ok(())
 }
@@ -0,0 +1,187 @@
+// the waveshare panel the firmware is built for, picked with PANEL in the .env. the pages only
+// see the canvas, the refreshes go through the driver here. the spec of the panel tells if it
+// can do a quick one, the others flash and are refreshed less often
+use std::time::{Duration, Instant};
+
+use display::panel::{self, Canvas, Panel};
+use display::DisplayBoxed;
+#[cfg(panel = "2in9bc")]
+use epd_waveshare::prelude::WaveshareThreeColorDisplay;
+use epd_waveshare::prelude::{QuickRefresh, RefreshLut, WaveshareDisplay};
+use esp_idf_hal::delay::Ets;
+use esp_idf_hal::gpio::{Gpio10, Gpio17, Gpio18, Input, Output, PinDriver};
+use esp_idf_hal::spi::{SpiDeviceDriver, SpiDriver};
+
+type Spi<'a> = SpiDeviceDriver<'a, SpiDriver<'a>>;
+type Busy<'a> = PinDriver<'a, Gpio10, Input>;
+type Dc<'a> = PinDriver<'a, Gpio18, Output>;
+type Rst<'a> = PinDriver<'a, Gpio17, Output>;
+
+#[cfg(panel = "2in9")]
+pub type Active = panel::Epd2in9;
+#[cfg(panel = "2in9")]
+type Epd<'a> = epd_waveshare::epd2in9::Epd2in9<Spi<'a>, Busy<'a>, Dc<'a>, Rst<'a>, Ets>;
+
+#[cfg(panel = "2in9_v2")]
+pub type Active = panel::Epd2in9V2;
+#[cfg(panel = "2in9_v2")]
+type Epd<'a> = epd_waveshare::epd2in9_v2::Epd2in9<Spi<'a>, Busy<'a>, Dc<'a>, Rst<'a>, Ets>;
+
+#[cfg(panel = "2in9bc")]
+pub type Active = panel::Epd2in9bc;
+#[cfg(panel = "2in9bc")]
+type Epd<'a> = epd_waveshare::epd2in9bc::Epd2in9bc<Spi<'a>, Busy<'a>, Dc<'a>, Rst<'a>, Ets>;
+
+#[cfg(panel = "4in2")]
+pub type Active = panel::Epd4in2;
+#[cfg(panel = "4in2")]
+type Epd<'a> = epd_waveshare::epd4in2::Epd4in2<Spi<'a>, Busy<'a>, Dc<'a>, Rst<'a>, Ets>;
+
+#[cfg(panel = "7in5")]
+pub type Active = panel::Epd7in5;
+#[cfg(panel = "7in5")]
+type Epd<'a> = epd_waveshare::epd7in5_v2::Epd7in5<Spi<'a>, Busy<'a>, Dc<'a>, Rst<'a>, Ets>;
+
+pub type PanelColor = <Active as Panel>::Color;
+pub type PanelDisplay = DisplayBoxed<Canvas<<Active as Panel>::Display>>;
+
+// a panel without a quick refresh shows the changes at most this often, it flashes every time
+const FULL_REFRESH_INTERVAL: Duration = Duration::from_secs(180);
+
+// the quick refresh of the panels that have one, it is only used if the spec says so. it draws
+// the difference to the last frame, which has to be kept after every refresh
+trait QuickFrames<'a> {
+    fn quick_refresh(&mut self, _spi: &mut Spi<'a>, _buffer: &[u8]) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!(
+            "{} has no quick refresh",
+            Active::SPEC.name
+        ))
+    }
+    fn keep_frame(&mut self, _spi: &mut Spi<'a>, _buffer: &[u8]) -> anyhow::Result<()> {
+        Ok(())
+    }
+    // the 4.2" has to be switched back to the lut of the full refresh
+    fn full_lut(&mut self, _spi: &mut Spi<'a>) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+macro_rules! quick_frames {
+    ($epd:ty) => {
+        impl<'a> QuickFrames<'a> for $epd {
+            fn quick_refresh(&mut self, spi: &mut Spi<'a>, buffer: &[u8]) -> anyhow::Result<()> {
+                self.set_lut(spi, &mut Ets, Some(RefreshLut::Quick))?;
+                self.update_new_frame(spi, buffer, &mut Ets)?;
+                self.display_new_frame(spi, &mut Ets)?;
+                self.update_old_frame(spi, buffer, &mut Ets)?;
+                Ok(())
+            }
+            fn keep_frame(&mut self, spi: &mut Spi<'a>, buffer: &[u8]) -> anyhow::Result<()> {
+                self.update_old_frame(spi, buffer, &mut Ets)?;
+                Ok(())
+            }
+            fn full_lut(&mut self, spi: &mut Spi<'a>) -> anyhow::Result<()> {
+                self.set_lut(spi, &mut Ets, Some(RefreshLut::Full))?;
+                Ok(())
+            }
+        }
+    };
+}
+
+quick_frames!(epd_waveshare::epd2in9_v2::Epd2in9<Spi<'a>, Busy<'a>, Dc<'a>, Rst<'a>, Ets>);
+quick_frames!(epd_waveshare::epd4in2::Epd4in2<Spi<'a>, Busy<'a>, Dc<'a>, Rst<'a>, Ets>);
+impl<'a> QuickFrames<'a>
+    for epd_waveshare::epd2in9::Epd2in9<Spi<'a>, Busy<'a>, Dc<'a>, Rst<'a>, Ets>
+{
+}
+impl<'a> QuickFrames<'a>
+    for epd_waveshare::epd2in9bc::Epd2in9bc<Spi<'a>, Busy<'a>, Dc<'a>, Rst<'a>, Ets>
+{
+}
+impl<'a> QuickFrames<'a>
+    for epd_waveshare::epd7in5_v2::Epd7in5<Spi<'a>, Busy<'a>, Dc<'a>, Rst<'a>, Ets>
+{
+}
+
+pub struct PanelDriver<'a> {
+    epd: Epd<'a>,
+    spi: Spi<'a>,
+    last_full: Option<Instant>,
+    // changes that wait for the next full refresh
+    pending: bool,
+}
+
+impl<'a> PanelDriver<'a> {
+    pub fn new(mut spi: Spi<'a>, busy: Busy<'a>, dc: Dc<'a>, rst: Rst<'a>) -> anyhow::Result<Self> {
+        let epd = match Epd::new(&mut spi, busy, dc, rst, &mut Ets, None) {
+            Ok(epd) => epd,
+            Err(e) => return Err(anyhow::Error::new(e)),
+        };
+        Ok(PanelDriver {
+            epd,
+            spi,
+            last_full: None,
+            pending: false,
+        })
+    }
+    // flashes the whole panel, this also cleans up what the partial refreshes leave behind
+    pub fn full_refresh(&mut self, display: &PanelDisplay) -> anyhow::Result<()> {
+        self.last_full = Some(Instant::now());
+        self.pending = false;
+        let buffer = Active::buffer(display.0.inner());
+        // the tri colour panel gets the red plane along with the black one
+        #[cfg(panel = "2in9bc")]
+        if let Some(chromatic) = Active::chromatic_buffer(display.0.inner()) {
+            self.epd
+                .update_color_frame(&mut self.spi, &mut Ets, buffer, chromatic)?;
+            self.epd.display_frame(&mut self.spi, &mut Ets)?;
+            return Ok(());
+        }
+        if Active::SPEC.partial_refresh {
+            self.epd.full_lut(&mut self.spi)?;
+        }
+        self.epd
+            .update_and_display_frame(&mut self.spi, buffer, &mut Ets)?;
+        // the quick refresh draws the difference to the old frame
+        if Active::SPEC.partial_refresh {
+            self.epd.keep_frame(&mut self.spi, buffer)?;
+        }
+        Ok(())
+    }
+    // for new values. the panels without a quick refresh wait until the last full one is old
+    // enough, refresh_pending catches up
+    pub fn partial_refresh(&mut self, display: &PanelDisplay) -> anyhow::Result<()> {
+        if Active::SPEC.partial_refresh {
+            let buffer = Active::buffer(display.0.inner());
+            return self.epd.quick_refresh(&mut self.spi, buffer);
+        }
+        self.pending = true;
+        self.refresh_pending(display)
+    }
+    // for input, which should not wait
+    pub fn refresh_now(&mut self, display: &PanelDisplay) -> anyhow::Result<()> {
+        if Active::SPEC.partial_refresh {
+            self.partial_refresh(display)
+        } else {
+            self.full_refresh(display)
+        }
+    }
+    pub fn refresh_pending(&mut self, display: &PanelDisplay) -> anyhow::Result<()> {
+        let due = self
+            .last_full
+            .map_or(true, |last| last.elapsed() >= FULL_REFRESH_INTERVAL);
+        if self.pending && due {
+            self.full_refresh(display)?;
+        }
+        Ok(())
+    }
+    pub fn sleep(&mut self) -> anyhow::Result<()> {
+        self.epd.sleep(&mut self.spi, &mut Ets)?;
+        Ok(())
+    }
+    // the panel forgets its frames while sleeping, so a full refresh has to follow
+    pub fn wake_up(&mut self) -> anyhow::Result<()> {
+        self.epd.wake_up(&mut self.spi, &mut Ets)?;
+        Ok(())
+    }
+}
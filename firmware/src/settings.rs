@@ -0,0 +1,44 @@
+// what the server configured at runtime, like the layout of the dashboard or the alert rules. it
+// is kept in the nvs, so the device comes back with it after a restart. a factory reset erases
+// it along with the wifi state
+use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs, NvsDefault};
+
+const NAMESPACE: &str = "dashboard";
+// the keys, nvs keys can not be longer than 15 characters
+pub const LAYOUT: &str = "layout";
+pub const ALERT_RULES: &str = "alert_rules";
+// strings in the nvs can not be much longer than this
+const MAX_LEN: usize = 4000;
+
+pub struct SettingsStore {
+    nvs: EspNvs<NvsDefault>,
+}
+
+impl SettingsStore {
+    pub fn new(partition: EspDefaultNvsPartition) -> anyhow::Result<Self> {
+        Ok(SettingsStore {
+            nvs: EspNvs::new(partition, NAMESPACE, true)?,
+        })
+    }
+    pub fn load(&self, key: &str) -> Option<String> {
+        let mut buf = vec![0; MAX_LEN + 1];
+        match self.nvs.get_str(key, &mut buf) {
+            Ok(value) => value.map(str::to_string),
+            Err(err) => {
+                log::info!("Error reading the stored {}: {}", key, err);
+                None
+            }
+        }
+    }
+    pub fn save(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+        if value.len() > MAX_LEN {
+            return Err(anyhow::anyhow!(
+                "{} too long to store: {}",
+                key,
+                value.len()
+            ));
+        }
+        self.nvs.set_str(key, value)?;
+        Ok(())
+    }
+}
@@ -7,11 +7,11 @@ use anyhow::anyhow;
 use esp_idf_svc::wifi::BlockingWifi;
 pub fn connect_to_wifi<'a>(
     modem: Modem,
+    nvs: EspDefaultNvsPartition,
     ssid: &'a str,
     pass: &'a str,
 ) -> anyhow::Result<BlockingWifi<EspWifi<'a>>> {
     let esp_sys_loop = EspSystemEventLoop::take()?;
-    let nvs = EspDefaultNvsPartition::take()?;
 
     let esp_wifi = EspWifi::new(modem, esp_sys_loop.clone(), Some(nvs))?;
     let mut blocking_wifi = BlockingWifi::wrap(esp_wifi, esp_sys_loop)?;
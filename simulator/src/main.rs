@@ -1,56 +1,123 @@
-use display::prototypes::types::data::Oneof;
+use display::alerts;
+use display::clock::Tick;
+use display::color::DisplayColor;
+use display::dashboard::{Changes, DashboardState};
+use display::layout::PageLayout;
+use display::night::NightConfig;
+use display::pages::{BatteryPage, DashboardPage, InputEvent, PageManager, PhasePage, SystemPage};
+use display::panel::{Canvas, Epd2in9V2, Panel, PanelSpec};
 use display::prototypes::types::Data;
+use display::system::SystemInfo;
+use display::tariff::Tariff;
 use display::*;
-use embedded_graphics::mono_font::MonoTextStyleBuilder;
+use embedded_graphics::pixelcolor::{BinaryColor, Rgb888};
 use embedded_graphics::prelude::*;
-use embedded_graphics::primitives::Rectangle;
-use embedded_graphics_simulator::{OutputSettingsBuilder, SimulatorDisplay};
-use epd_waveshare::color::Color;
+use embedded_graphics_simulator::sdl2::Keycode;
+use embedded_graphics_simulator::{OutputSettingsBuilder, SimulatorDisplay, SimulatorEvent};
+use epd_waveshare::color::{Color, TriColor};
 use prost::Message;
 
 use anyhow::anyhow;
 // we need this to be able to extend the DisplayBoxed to be able to return the inner simulator
 // display to be able to pass that to the window.update function
-trait SimulatorDisplayInner {
-    fn inner_simulator_display(&self) -> &SimulatorDisplay<Color>;
+trait SimulatorDisplayInner<C: PixelColor> {
+    fn inner_simulator_display(&self) -> &SimulatorDisplay<C>;
 }
 
-impl SimulatorDisplayInner for DisplayBoxed<SimulatorDisplay<Color>> {
-    fn inner_simulator_display(&self) -> &SimulatorDisplay<Color> {
-        &self.0
+impl<C: PixelColor> SimulatorDisplayInner<C> for DisplayBoxed<Canvas<SimulatorDisplay<C>>> {
+    fn inner_simulator_display(&self) -> &SimulatorDisplay<C> {
+        self.0.inner()
     }
 }
-#[derive(Debug)]
-struct PrevText {
-    house_pow: String,
-    bat_charge: String,
-    inverter_pow: String,
-    grid_pow: String,
-    ts: String,
+
+// "--panel 7in5" or "--panel=7in5" picks the size of the window, the 2.9" v2 if not given
+fn panel_from_args() -> anyhow::Result<PanelSpec> {
+    let args: Vec<String> = std::env::args().collect();
+    let name = args
+        .iter()
+        .position(|arg| arg == "--panel")
+        .and_then(|idx| args.get(idx + 1).cloned())
+        .or_else(|| {
+            args.iter()
+                .find_map(|arg| arg.strip_prefix("--panel=").map(str::to_string))
+        });
+    match name {
+        Some(name) => PanelSpec::by_name(&name).ok_or(anyhow!(
+            "unknown panel {}, the panels are: {}",
+            name,
+            display::panel::PANELS.map(|spec| spec.name).join(", ")
+        )),
+        None => Ok(Epd2in9V2::SPEC),
+    }
 }
-impl Default for PrevText {
-    fn default() -> Self {
-        PrevText {
-            house_pow: "0.00".to_string(),
-            bat_charge: "0.00".to_string(),
-            inverter_pow: "0.00".to_string(),
-            grid_pow: "0.00".to_string(),
-            ts: "0:00".to_string(),
+
+// LAYOUT_FILE replaces the dashboard with the widgets of a layout file. the file is read again
+// once it changes, so the widgets can be moved around while the simulator runs
+struct LayoutFile {
+    path: std::path::PathBuf,
+    modified: Option<std::time::SystemTime>,
+    // of the panel, the regions are checked against it
+    size: Size,
+}
+
+impl LayoutFile {
+    fn from_env(size: Size) -> Option<Self> {
+        std::env::var("LAYOUT_FILE").ok().map(|path| LayoutFile {
+            path: path.into(),
+            modified: None,
+            size,
+        })
+    }
+    // the layout once the file changed, a broken file keeps the current layout
+    fn reload(&mut self) -> Option<PageLayout> {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        let layout = std::fs::read_to_string(&self.path)
+            .map_err(anyhow::Error::from)
+            .and_then(|config| {
+                PageLayout::parse_for(&config, self.size).map_err(anyhow::Error::from)
+            });
+        match layout {
+            Ok(layout) => {
+                println!("loaded layout from {}", self.path.display());
+                Some(layout)
+            }
+            Err(err) => {
+                eprintln!(
+                    "Error in layout configuration, keeping the current one: {}",
+                    err
+                );
+                None
+            }
         }
     }
 }
-#[derive(Debug)]
-struct PrevConnections {
-    battery: String,
-    grid: String,
-    sun_inverter: String,
-}
 
 pub fn main() -> anyhow::Result<()> {
     dioxus_devtools::connect_subsecond();
-    let display_raw: SimulatorDisplay<Color> =
-        embedded_graphics_simulator::SimulatorDisplay::new(Size::new(296, 128));
-    let mut display = DisplayBoxed(Box::new(display_raw));
+    let panel = panel_from_args()?;
+    println!("simulating the {} panel", panel.name);
+    // the tri colour panels show the flows and alerts in red
+    if panel.tri_color {
+        run::<TriColor>(panel)
+    } else {
+        run::<Color>(panel)
+    }
+}
+
+// the simulator display starts out from binary colours and the window converts from and to rgb
+fn run<C>(panel: PanelSpec) -> anyhow::Result<()>
+where
+    C: DisplayColor + From<BinaryColor> + From<Rgb888> + Into<Rgb888> + 'static,
+{
+    let display_raw: SimulatorDisplay<C> =
+        embedded_graphics_simulator::SimulatorDisplay::new(panel.size());
+    let mut display = DisplayBoxed::new(Canvas::new(display_raw));
     let mut window = embedded_graphics_simulator::Window::new(
         "E-Paper Simulator",
         &OutputSettingsBuilder::new()
@@ -58,11 +125,76 @@ pub fn main() -> anyhow::Result<()> {
             .build(),
     );
 
-    let default_text_style = MonoTextStyleBuilder::new()
-        .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
-        .text_color(Color::Black)
-        .build();
-    display.draw_default_display(default_text_style)?;
+    // the tariff and what the totals box shows can be configured through env vars
+    let totals_mode = TotalsMode::parse(&std::env::var("TOTALS_MODE").unwrap_or_default());
+    let tariff = match std::env::var("TARIFF_IMPORT") {
+        Ok(import) => Tariff::parse(
+            &import,
+            &std::env::var("TARIFF_FEED_IN").unwrap_or("0.08".to_string()),
+            &std::env::var("TARIFF_BANDS").unwrap_or_default(),
+        )
+        .unwrap_or_else(|err| {
+            eprintln!("Error in tariff configuration, using the default: {}", err);
+            Tariff::default()
+        }),
+        Err(_) => Tariff::default(),
+    };
+    // the clock of the dashboard counts from the start of the simulator
+    let started = std::time::Instant::now();
+    let mut state = DashboardState::new(tariff, totals_mode);
+    // there is no device, so the system page shows made up values
+    state.system = SystemInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        build_hash: "simulator".to_string(),
+        free_heap: Some(183 * 1024),
+        ssid: "Simulator".to_string(),
+        rssi: Some(-61),
+        ip: Some("127.0.0.1".to_string()),
+        server: "localhost:6600".to_string(),
+        battery_voltage: Some(3.92),
+        ..SystemInfo::new(Tick::default())
+    };
+
+    // rules like "battery<10,import>5@10m,error,nodata@15m", see alerts::Rule::parse
+    if let Ok(rules) = std::env::var("ALERT_RULES") {
+        match alerts::rules_or_default(&rules) {
+            Ok(rules) => state.alerts.set_rules(rules),
+            Err(err) => eprintln!("Error in alert rules, using the default: {}", err),
+        }
+    }
+    // the night follows the sun unless quiet hours like "22:00-06:30" are set
+    match NightConfig::parse(
+        &std::env::var("QUIET_HOURS").unwrap_or_default(),
+        &std::env::var("NIGHT_REFRESH_MINS").unwrap_or_default(),
+    ) {
+        Ok(config) => state.night.set_config(config),
+        Err(err) => eprintln!("Error in night configuration, using the default: {}", err),
+    }
+
+    // the keys stand in for the buttons of the device: right/n next page, left previous page
+    // and enter/space select
+    let mut pages = PageManager::new(Box::new(DashboardPage::new()))
+        .with_page(Box::new(PhasePage))
+        .with_page(Box::new(BatteryPage))
+        .with_page(Box::new(SystemPage));
+    if let Some(secs) = std::env::var("PAGE_ROTATION_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+    {
+        pages = pages.with_rotation(std::time::Duration::from_secs(secs));
+    }
+    // the larger panels show the detail pages around the dashboard
+    if let Some(layout) = PageLayout::for_panel(&panel) {
+        pages.set_page(0, Box::new(layout?.page()));
+    }
+    state.panel_size = panel.size();
+    let mut layout_file = LayoutFile::from_env(panel.size());
+    if let Some(layout) = layout_file.as_mut().and_then(LayoutFile::reload) {
+        pages.set_page(0, Box::new(layout.page()));
+        state.layout = Some(layout);
+    }
+
+    pages.draw(&mut display, &state)?;
     window.update(display.inner_simulator_display());
     let (mut socket, response) = tungstenite::connect(format!("ws://localhost:6600/subscribe"))
         .map_err(|err| anyhow!("Error trying to connect to server {:?}", err))?;
@@ -71,39 +203,79 @@ pub fn main() -> anyhow::Result<()> {
         return Err(anyhow!("Error: {:?}", response.status()));
     }
 
-    display.set_connected()?;
+    // reads only block shortly, so keys and alerts are handled while the server is quiet
+    if let tungstenite::stream::MaybeTlsStream::Plain(stream) = socket.get_mut() {
+        stream.set_read_timeout(Some(std::time::Duration::from_millis(50)))?;
+    }
 
-    let mut prevs = PrevText::default();
-    let mut prev_connections = PrevConnections {
-        battery: "0.00".to_string(),
-        grid: "0.00".to_string(),
-        sun_inverter: "0.00".to_string(),
-    };
+    state.connected = true;
+    pages.update(
+        &mut display,
+        &state,
+        Changes {
+            connection: true,
+            ..Default::default()
+        },
+    )?;
 
-    let mut flushed = true;
-    let mut rescaled = [0.0; 288];
     'outer: loop {
         for event in window.events() {
-            if event == embedded_graphics_simulator::SimulatorEvent::Quit {
-                println!("Quit event");
-                break 'outer;
+            match event {
+                SimulatorEvent::Quit => {
+                    println!("Quit event");
+                    break 'outer;
+                }
+                SimulatorEvent::KeyDown { keycode, .. } => {
+                    let input = match keycode {
+                        Keycode::Right | Keycode::N => InputEvent::Next,
+                        Keycode::Left => InputEvent::Previous,
+                        Keycode::Return | Keycode::Space => InputEvent::Select,
+                        _ => continue,
+                    };
+                    // at night the first key only brings the pages back, with a notice it
+                    // only closes the notice
+                    let changes = state.wake(Tick::since(started));
+                    if changes.night || changes.notice {
+                        pages.update(&mut display, &state, changes)?;
+                        continue;
+                    }
+                    pages.handle(input, &mut display, &state, Tick::since(started))?;
+                    println!("showing page: {}", pages.title());
+                }
+                _ => {}
             }
         }
+        if pages.tick(Tick::since(started), &mut display, &state)? {
+            println!("rotated to page: {}", pages.title());
+        }
+        let changes = state.tick(Tick::since(started));
+        if changes.night {
+            println!("night mode: {}", state.night.is_active());
+        }
+        if changes.alert || changes.night || changes.notice {
+            pages.update(&mut display, &state, changes)?;
+        }
+        if let Some(layout) = layout_file.as_mut().and_then(LayoutFile::reload) {
+            state.layout = Some(layout);
+            let changes = Changes {
+                layout: true,
+                ..Default::default()
+            };
+            pages.update(&mut display, &state, changes)?;
+        }
         match socket.read() {
             Ok(message) => match message {
                 tungstenite::Message::Binary(data) => match Data::decode(data) {
                     Ok(ref data_enum) => {
+                        println!("received data, starting to render");
+                        let changes = state.apply(data_enum, Tick::since(started));
+                        if let Some(invalid) = changes.invalid {
+                            eprintln!("invalid data: {}", invalid);
+                        }
                         // this function is hot patched once it changes, thanks to the subsecond
                         // crate
                         dioxus_devtools::subsecond::call(|| {
-                            match render(
-                                &mut display,
-                                data_enum,
-                                &mut prev_connections,
-                                &mut prevs,
-                                &mut flushed,
-                                &mut rescaled,
-                            ) {
+                            match pages.update(&mut display, &state, changes) {
                                 Ok(_) => {}
                                 Err(e) => {
                                     eprintln!("Error occured while rendering: {:?}", e);
@@ -113,13 +285,27 @@ pub fn main() -> anyhow::Result<()> {
                     }
 
                     Err(e) => {
+                        state.system.decode_errors += 1;
                         eprintln!("Error decoding message: {:?}", e);
                     }
                 },
+                // plain text is shown as a notice
+                tungstenite::Message::Text(text) => {
+                    let changes = state.show_text(&text, Tick::since(started));
+                    if let Some(invalid) = changes.invalid {
+                        eprintln!("invalid text message: {}", invalid);
+                    }
+                    pages.update(&mut display, &state, changes)?;
+                }
                 random_message => {
                     println!("Message: {:?}", random_message);
                 }
             },
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
             Err(e) => {
                 eprintln!("Error reading from socket: {:?}", e);
             }
@@ -129,170 +315,3 @@ pub fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
-fn render(
-    display: &mut DisplayBoxed<SimulatorDisplay<Color>>,
-    data: &prototypes::types::Data,
-    prev_connections: &mut PrevConnections,
-    prevs: &mut PrevText,
-    flushed: &mut bool,
-    rescaled: &mut [f32; 288],
-) -> anyhow::Result<()> {
-    println!("received data, starting to render");
-    let default_text_style = MonoTextStyleBuilder::new()
-        .font(&embedded_graphics::mono_font::ascii::FONT_6X10)
-        .text_color(Color::Black)
-        .build();
-    match &data.oneof {
-        Some(Oneof::UiData(ui_data)) => {
-            display.clear_text()?;
-            let house_pow = match &ui_data.gui_house_pow {
-                Some(v) => {
-                    prevs.house_pow = v.clone();
-                    v
-                }
-                None => &prevs.house_pow,
-            };
-            let bat_charge = match &ui_data.gui_bat_data_fuel_charge {
-                Some(v) => {
-                    prevs.bat_charge = v.clone();
-                    v
-                }
-                None => &prevs.bat_charge,
-            };
-            let inverter_pow = match &ui_data.gui_inverter_power {
-                Some(v) => {
-                    prevs.inverter_pow = v.clone();
-                    v
-                }
-                None => &prevs.inverter_pow,
-            };
-            let grid_pow = match &ui_data.gui_grid_pow {
-                Some(v) => {
-                    prevs.grid_pow = v.clone();
-                    v
-                }
-                None => &prevs.grid_pow,
-            };
-            let ts = match &ui_data.ts {
-                Some(v) => {
-                    prevs.ts = v.clone();
-                    v
-                }
-                None => &prevs.ts,
-            };
-            let bat_power = match &ui_data.gui_bat_data_power {
-                Some(v) => {
-                    prev_connections.battery = v.to_string();
-                    v
-                }
-                None => prev_connections.battery.as_str(),
-            };
-            display.draw_text(
-                default_text_style,
-                &house_pow,
-                &if bat_power != "0.00" && !bat_power.starts_with("-") {
-                    // bat_power is the current going to the battery,
-                    // therefore if non 0 and not starting with a minus we
-                    // are charging
-                    format!("+{}", bat_charge)
-                } else if bat_power.starts_with("-") && bat_power != "-0.00" {
-                    // in this case we are discharging
-                    format!("-{}", bat_charge)
-                } else {
-                    // no current flowing in or out of the battery
-                    format!("{}", bat_charge)
-                },
-                &inverter_pow,
-                &match grid_pow.starts_with("-") {
-                    true => format!("{}", grid_pow),
-                    false => format!("+{}", grid_pow),
-                },
-                &ts,
-            )?;
-
-            // clearing the connections
-            display.fill_solid(
-                &Rectangle::new(Point::new(54, 43), Size::new(42, 41)),
-                Color::White,
-            )?;
-            // to the house always active
-            display.draw_connections(ConnectionDirection::Top(true))?;
-
-            // we could also just check for changes and clear the arrows
-            // individually to avoid this copying and redrawing
-            // will do that in the future
-
-            let grid = match &ui_data.gui_grid_pow {
-                Some(v) => {
-                    prev_connections.grid = v.clone();
-                    v
-                }
-                None => &prev_connections.grid,
-            };
-            let sun_inverter = match &ui_data.gui_inverter_power {
-                Some(v) => {
-                    prev_connections.sun_inverter = v.clone();
-                    v
-                }
-                None => &prev_connections.sun_inverter,
-            };
-
-            if bat_power != "0.00" && !bat_power.starts_with("-") {
-                display.draw_connections(ConnectionDirection::Bottom(true))?;
-            } else if bat_power != "0.00" && bat_power.starts_with("-") {
-                display.draw_connections(ConnectionDirection::Bottom(false))?;
-            }
-
-            if grid != "0.00" && !grid.starts_with("-") {
-                display.draw_connections(ConnectionDirection::Right(false))?;
-            } else if grid != "0.00" && grid.starts_with("-") {
-                display.draw_connections(ConnectionDirection::Right(true))?;
-            }
-
-            if sun_inverter != "0.00" && !sun_inverter.starts_with("-") {
-                display.draw_connections(ConnectionDirection::Left(false))?;
-            } else if sun_inverter != "-0.00" && sun_inverter.starts_with("-") {
-                display.draw_connections(ConnectionDirection::Left(false))?;
-            }
-
-            if let Some(weather) = &ui_data.weather {
-                if let Some(daily) = &weather.daily {
-                    let sunrise = daily
-                        .sunrise
-                        .first()
-                        .ok_or(anyhow!("missing sunrise values"))?;
-                    let sunset = daily
-                        .sunset
-                        .first()
-                        .ok_or(anyhow!("missing sunset values"))?;
-                    display.update_sun_data(sunrise, sunset)?;
-                }
-                if let Some(hourly) = &weather.hourly {
-                    display.update_weather_data(hourly.clone())?;
-                }
-            }
-            if let Some(total_data) = &ui_data.total_data {
-                if total_data.new || *flushed {
-                    display.update_total_new(&total_data.consumption, &total_data.generated)?;
-                }
-            }
-        }
-        Some(Oneof::Prediction(prediction)) => {
-            println!("got prediction: {:?}", prediction);
-            if prediction.prediction.len() != 288 {
-                return Ok(());
-            }
-            // rescaling the values. over the wire the values were encoded using i32
-            // instead of f32. since the values are in the range of 0-12 this saves
-            // stream bandwidth
-            for (i, v) in prediction.prediction.iter().enumerate() {
-                rescaled[i] = *v as f32 / 1000.0;
-            }
-            display.update_chart(rescaled)?;
-        }
-        None => {
-            eprintln!("Data that was received is None")
-        }
-    }
-    Ok(())
-}